@@ -0,0 +1,234 @@
+use crate::{
+    output::{Format, Printer},
+    walk::{self, sources},
+};
+use anyhow::{anyhow, Error, Result};
+use clex::{
+    grep::{Match, Pattern},
+    Lexeme, Lexer, Location, Token,
+};
+use regex::Regex;
+use serde::Serialize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+
+/// Token kind to search in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Identifier,
+    Keyword,
+    Symbol,
+    String,
+    Char,
+    Comment,
+    Int,
+    Float,
+}
+
+impl std::str::FromStr for Kind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "identifier" => Self::Identifier,
+            "keyword" => Self::Keyword,
+            "symbol" => Self::Symbol,
+            "string" => Self::String,
+            "char" => Self::Char,
+            "comment" => Self::Comment,
+            "int" => Self::Int,
+            "float" => Self::Float,
+            _ => return Err(anyhow!("Unknown token kind: {}", s)),
+        })
+    }
+}
+
+impl Kind {
+    fn matches(&self, lexeme: &Lexeme) -> bool {
+        match self {
+            Self::Identifier => lexeme.token == Token::Identifier,
+            Self::Keyword => lexeme.keyword().is_some(),
+            Self::Symbol => lexeme.token == Token::Symbol,
+            Self::String => lexeme.token == Token::String,
+            Self::Char => lexeme.token == Token::Char,
+            Self::Comment => lexeme.token == Token::Comment,
+            Self::Int => lexeme.token == Token::Int,
+            Self::Float => lexeme.token == Token::Float,
+        }
+    }
+}
+
+#[derive(structopt::StructOpt)]
+pub struct Args {
+    /// Search regular expression in tokens of kinds (identifier, keyword, symbol, string, char,
+    /// comment, int, float) instead of matching token sequence
+    #[structopt(long = "in", require_delimiter = true)]
+    pub kinds: Vec<Kind>,
+
+    /// Number of context lines around matches
+    #[structopt(short = "C", long, default_value = "0")]
+    pub context: usize,
+
+    /// Output format (table, json, jsonl, csv)
+    #[structopt(short = "F", long, default_value = "table")]
+    pub format: Format,
+
+    #[structopt(flatten)]
+    pub walk: walk::Args,
+
+    /// Token sequence pattern like `memcpy($dst, $src, sizeof($_))` or regular expression
+    pub pattern: String,
+
+    /// C source files or directories (`-` or `@file` to read list of paths)
+    #[structopt(required = true)]
+    pub paths: Vec<PathBuf>,
+}
+
+enum Matcher {
+    Tokens(Pattern),
+    Regex(Regex, Vec<Kind>),
+}
+
+impl Matcher {
+    fn find(&self, src: &str) -> Vec<Match> {
+        match self {
+            Self::Tokens(pattern) => pattern.find(&Lexer::from(src).collect::<Vec<_>>()),
+            Self::Regex(regex, kinds) => Lexer::from(src)
+                .filter(|lexeme| kinds.iter().any(|kind| kind.matches(lexeme)))
+                .flat_map(|lexeme| {
+                    regex
+                        .find_iter(lexeme.slice)
+                        .map(|found| Match {
+                            location: lexeme.location.advance(&lexeme.slice[..found.start()]),
+                            span: lexeme.span.start + found.start()
+                                ..lexeme.span.start + found.end(),
+                            bindings: Vec::new(),
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    file: &'a Path,
+    line: u32,
+    column: u32,
+    end_line: u32,
+    end_column: u32,
+    text: &'a str,
+    bindings: BTreeMap<&'a str, &'a str>,
+    before: Vec<&'a str>,
+    after: Vec<&'a str>,
+}
+
+pub fn run(args: &Args) -> Result<()> {
+    let matcher = if args.kinds.is_empty() {
+        Matcher::Tokens(
+            args.pattern
+                .parse()
+                .map_err(|_| anyhow!("Invalid token pattern: {:?}", args.pattern))?,
+        )
+    } else {
+        Matcher::Regex(Regex::new(&args.pattern)?, args.kinds.clone())
+    };
+
+    let sources = sources(&args.walk, &args.paths)?;
+    let mut printer = Printer::new(args.format, &["file", "line", "column", "text"]);
+    let mut separate = false;
+
+    for path in &sources {
+        let src = args.walk.read(path)?;
+        let lines = src.lines().collect::<Vec<_>>();
+        // Table output: columns of matches by first line, lines of matches and lines to print
+        let mut starts = BTreeMap::<usize, Vec<u32>>::new();
+        let mut matched = BTreeSet::new();
+        let mut shown = BTreeSet::new();
+
+        for found in matcher.find(&src) {
+            let text = &src[found.span.clone()];
+            let end = found.location.advance(text);
+            let first = found.location.line as usize - 1;
+            let last = end.line as usize - 1;
+
+            if args.format == Format::Table {
+                starts.entry(first).or_default().push(found.location.column);
+                matched.extend(first..=last);
+                shown.extend(
+                    first.saturating_sub(args.context)..(last + args.context + 1).min(lines.len()),
+                );
+                continue;
+            }
+
+            print_match(&mut printer, args, path, &src, &lines, &found, end)?;
+        }
+
+        // every match is reported even when its line is context of another one
+        let mut next = None;
+        for index in shown {
+            if args.context > 0 && separate && next != Some(index) {
+                println!("--");
+            }
+            let line = lines[index];
+            if let Some(columns) = starts.get(&index) {
+                for column in columns {
+                    println!("{}:{}:{}: {}", path.display(), index + 1, column, line);
+                }
+            } else if matched.contains(&index) {
+                println!("{}:{}: {}", path.display(), index + 1, line);
+            } else {
+                println!("{}-{}- {}", path.display(), index + 1, line);
+            }
+            next = Some(index + 1);
+            separate = true;
+        }
+    }
+
+    if args.format != Format::Table {
+        printer.finish()?;
+    }
+
+    Ok(())
+}
+
+fn print_match(
+    printer: &mut Printer,
+    args: &Args,
+    path: &Path,
+    src: &str,
+    lines: &[&str],
+    found: &Match,
+    end: Location,
+) -> Result<()> {
+    let first = found.location.line as usize - 1;
+    let last = end.line as usize - 1;
+    let record = Record {
+        file: path,
+        line: found.location.line,
+        column: found.location.column,
+        end_line: end.line,
+        end_column: end.column,
+        text: &src[found.span.clone()],
+        bindings: found
+            .bindings
+            .iter()
+            .map(|(name, span)| (name.as_str(), &src[span.clone()]))
+            .collect(),
+        before: lines[first.saturating_sub(args.context)..first].to_vec(),
+        after: lines[(last + 1).min(lines.len())..(last + 1 + args.context).min(lines.len())]
+            .to_vec(),
+    };
+
+    printer.record(&record, || {
+        vec![
+            record.file.display().to_string(),
+            record.line.to_string(),
+            record.column.to_string(),
+            record.text.into(),
+        ]
+    })
+}
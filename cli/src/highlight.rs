@@ -0,0 +1,86 @@
+use crate::walk::{self, sources};
+use anyhow::Result;
+use clex::highlight::{ansi, document, escape, html, Options};
+use std::path::PathBuf;
+
+#[derive(structopt::StructOpt)]
+pub struct Args {
+    /// Write ANSI colored text instead of HTML
+    #[structopt(short = "a", long)]
+    pub ansi: bool,
+
+    /// Prefix lines with numbers
+    #[structopt(short = "n", long)]
+    pub line_numbers: bool,
+
+    /// Add line anchors (`#L<line>`, or `#<index>-L<line>` for multiple files) to HTML
+    #[structopt(short = "A", long)]
+    pub anchors: bool,
+
+    /// Write only `<pre>` blocks without HTML document and style sheet
+    #[structopt(long)]
+    pub fragment: bool,
+
+    /// Output file instead of stdout
+    #[structopt(short = "o", long)]
+    pub output: Option<PathBuf>,
+
+    #[structopt(flatten)]
+    pub walk: walk::Args,
+
+    /// C source files or directories (`-` or `@file` to read list of paths)
+    #[structopt(required = true)]
+    pub paths: Vec<PathBuf>,
+}
+
+pub fn run(args: &Args) -> Result<()> {
+    let sources = sources(&args.walk, &args.paths)?;
+    let multiple = sources.len() > 1;
+    let mut out = String::new();
+
+    for (index, path) in sources.iter().enumerate() {
+        let src = args.walk.read(path)?;
+        let prefix = if multiple {
+            format!("{}-", index + 1)
+        } else {
+            String::new()
+        };
+        let options = Options {
+            line_numbers: args.line_numbers,
+            anchors: Some(prefix.clone()).filter(|_| args.anchors),
+            ..Options::default()
+        };
+
+        if args.ansi {
+            if multiple {
+                out.push_str(&format!("\x1b[1m==> {} <==\x1b[0m\n", path.display()));
+            }
+            out.push_str(&ansi(&src, &options));
+        } else {
+            if multiple {
+                out.push_str(&format!(
+                    "<h3 id=\"{}\">{}</h3>\n",
+                    escape(&prefix),
+                    escape(&path.display().to_string())
+                ));
+            }
+            out.push_str(&html(&src, &options));
+        }
+    }
+
+    if !args.ansi && !args.fragment {
+        let title = match sources.as_slice() {
+            [path] => path.display().to_string(),
+            _ => "clex".into(),
+        };
+        out = document(&title, &out);
+    }
+
+    if let Some(path) = &args.output {
+        std::fs::write(path, out)?;
+    } else {
+        print!("{}", out);
+    }
+
+    Ok(())
+}
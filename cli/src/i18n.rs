@@ -0,0 +1,67 @@
+use crate::walk::{self, sources};
+use anyhow::{anyhow, Result};
+use clex::i18n::{extract, Catalog, Options, Spec};
+use std::path::PathBuf;
+
+#[derive(structopt::StructOpt)]
+pub struct Args {
+    /// Additional keyword function specs (`NAME`, `NAME:ARG`, `NAME:1,2`, `NAME:1c,2`)
+    #[structopt(short = "k", long = "keyword", number_of_values = 1)]
+    pub keywords: Vec<String>,
+
+    /// Do not use default keywords
+    #[structopt(short = "K", long)]
+    pub no_default_keywords: bool,
+
+    /// Tag of comments for translators (empty to take all preceding comments)
+    #[structopt(short = "c", long, default_value = "TRANSLATORS:")]
+    pub add_comments: String,
+
+    /// Output file instead of stdout
+    #[structopt(short = "o", long)]
+    pub output: Option<PathBuf>,
+
+    #[structopt(flatten)]
+    pub walk: walk::Args,
+
+    /// C source files or directories (`-` or `@file` to read list of paths)
+    #[structopt(required = true)]
+    pub paths: Vec<PathBuf>,
+}
+
+pub fn run(args: &Args) -> Result<()> {
+    let mut options = Options {
+        comment_tag: Some(args.add_comments.clone()),
+        ..Options::default()
+    };
+
+    if args.no_default_keywords {
+        options.keywords.clear();
+    }
+
+    for keyword in &args.keywords {
+        options.keywords.push(
+            keyword
+                .parse::<Spec>()
+                .map_err(|_| anyhow!("Invalid keyword spec: {:?}", keyword))?,
+        );
+    }
+
+    let sources = sources(&args.walk, &args.paths)?;
+    let mut catalog = Catalog::default();
+
+    for path in &sources {
+        let src = args.walk.read(path)?;
+        catalog.add(&path.display().to_string(), extract(&src, &options));
+    }
+
+    let pot = catalog.to_string();
+
+    if let Some(path) = &args.output {
+        std::fs::write(path, pot)?;
+    } else {
+        print!("{}", pot);
+    }
+
+    Ok(())
+}
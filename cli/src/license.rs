@@ -0,0 +1,123 @@
+use crate::{
+    output::{Format, Printer},
+    walk::{self, sources},
+};
+use anyhow::Result;
+use clex::license::{scan, Expr};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+#[derive(structopt::StructOpt)]
+pub struct Args {
+    /// Print only files with issues
+    #[structopt(short = "I", long)]
+    pub only_issues: bool,
+
+    /// Output format (table, json, jsonl, csv)
+    #[structopt(short = "F", long, default_value = "table")]
+    pub format: Format,
+
+    #[structopt(flatten)]
+    pub walk: walk::Args,
+
+    /// C source files or directories (`-` or `@file` to read list of paths)
+    #[structopt(required = true)]
+    pub paths: Vec<PathBuf>,
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    file: &'a Path,
+    license: Option<String>,
+    expression: Option<Value>,
+    licenses: Vec<LicenseRecord>,
+    copyrights: Vec<CopyrightRecord>,
+    issues: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct LicenseRecord {
+    text: String,
+    line: u32,
+    column: u32,
+}
+
+#[derive(Serialize)]
+struct CopyrightRecord {
+    years: Vec<(u16, u16)>,
+    holder: String,
+    line: u32,
+    column: u32,
+}
+
+fn expr_json(expr: &Expr) -> Value {
+    match expr {
+        Expr::License { id, plus } => json!({ "license": id, "plus": plus }),
+        Expr::With { license, exception } => {
+            json!({ "with": expr_json(license), "exception": exception })
+        }
+        Expr::And(left, right) => json!({ "and": [expr_json(left), expr_json(right)] }),
+        Expr::Or(left, right) => json!({ "or": [expr_json(left), expr_json(right)] }),
+    }
+}
+
+pub fn run(args: &Args) -> Result<()> {
+    let sources = sources(&args.walk, &args.paths)?;
+    let mut printer = Printer::new(args.format, &["file", "license", "copyrights", "issues"]);
+
+    for path in &sources {
+        let src = args.walk.read(path)?;
+        let info = scan(&src);
+
+        if args.only_issues && info.issues.is_empty() {
+            continue;
+        }
+
+        let record = Record {
+            file: path,
+            license: info.license().map(|expr| expr.to_string()),
+            expression: info.license().map(expr_json),
+            licenses: info
+                .licenses
+                .iter()
+                .map(|license| LicenseRecord {
+                    text: license.text.clone(),
+                    line: license.location.line,
+                    column: license.location.column,
+                })
+                .collect(),
+            copyrights: info
+                .copyrights
+                .iter()
+                .map(|copyright| CopyrightRecord {
+                    years: copyright
+                        .years
+                        .iter()
+                        .map(|years| (*years.start(), *years.end()))
+                        .collect(),
+                    holder: copyright.holder.clone(),
+                    line: copyright.location.line,
+                    column: copyright.location.column,
+                })
+                .collect(),
+            issues: info.issues.iter().map(|issue| issue.to_string()).collect(),
+        };
+
+        printer.record(&record, || {
+            vec![
+                record.file.display().to_string(),
+                record.license.clone().unwrap_or_else(|| "-".into()),
+                record
+                    .copyrights
+                    .iter()
+                    .map(|copyright| copyright.holder.as_str())
+                    .collect::<Vec<_>>()
+                    .join("; "),
+                record.issues.join("; "),
+            ]
+        })?;
+    }
+
+    printer.finish()
+}
@@ -1,116 +1,397 @@
-use anyhow::Result;
-use clex::{Lexeme, Lexer, Token};
+mod grep;
+mod highlight;
+mod i18n;
+mod license;
+mod output;
+mod stats;
+mod tags;
+mod todo;
+mod walk;
+
+use anyhow::{anyhow, Result};
+use clex::{compdb, ByteSource, Diagnostic, Dialect, Lexeme, Lexer, StreamLexer, Token};
+use output::{Format, Printer, Record};
+use serde::Serialize;
+use serde_json::{json, Value};
 use std::{
     fs::File,
+    io::{BufReader, ErrorKind},
     path::{Path, PathBuf},
 };
+use walk::Entry;
+
+#[derive(Serialize)]
+struct TokenRecord<'a> {
+    file: &'a Path,
+    kind: String,
+    start: usize,
+    end: usize,
+    line: u32,
+    column: u32,
+    slice: &'a str,
+    value: Option<Value>,
+}
+
+/// Decoded value of lexeme
+fn lexeme_value(lexeme: &Lexeme, dialect: Dialect) -> Option<Value> {
+    match lexeme.token {
+        Token::Identifier => lexeme
+            .keyword_in(dialect)
+            .map(|keyword| json!(keyword.as_str())),
+        Token::Comment => lexeme.comment().map(Value::from),
+        Token::Char => lexeme.char().map(|chr| json!(chr)),
+        Token::String => lexeme.string().map(Value::from),
+        Token::Int => lexeme
+            .int::<i128>()
+            .map(|int| json!(int))
+            .or_else(|| lexeme.int::<u128>().map(|int| json!(int))),
+        Token::Float => lexeme.float::<f64>().map(|float| json!(float)),
+        _ => None,
+    }
+}
+
+fn lexeme_record(format: Format, path: &Path, lexeme: &Lexeme, dialect: Dialect) -> Result<Record> {
+    let record = TokenRecord {
+        file: path,
+        kind: format!("{:?}", lexeme.token),
+        start: lexeme.span.start,
+        end: lexeme.span.end,
+        line: lexeme.location.line,
+        column: lexeme.location.column,
+        slice: lexeme.slice,
+        value: lexeme_value(lexeme, dialect),
+    };
+
+    Record::new(format, &record, || {
+        vec![
+            record.file.display().to_string(),
+            record.kind.clone(),
+            record.start.to_string(),
+            record.end.to_string(),
+            record.line.to_string(),
+            record.column.to_string(),
+            record.slice.into(),
+            match &record.value {
+                Some(Value::String(value)) => value.clone(),
+                Some(value) => value.to_string(),
+                None => String::new(),
+            },
+        ]
+    })
+}
+
+/// Buffered output of single entry
+#[derive(Default)]
+struct Output {
+    items: Vec<Item>,
+    stats: Stats,
+}
+
+enum Item {
+    /// Standard output line
+    Out(String),
+    /// Standard error line
+    Err(String),
+    /// Structured record
+    Record(Record),
+}
 
-fn is_c(path: &Path) -> bool {
-    path.extension().map(|ext| ext == "c").unwrap_or(false)
+impl Output {
+    fn out(&mut self, line: String) {
+        self.items.push(Item::Out(line));
+    }
+
+    fn err(&mut self, line: String) {
+        self.items.push(Item::Err(line));
+    }
+
+    fn emit(self, printer: &mut Printer, stats: &mut Stats) -> Result<()> {
+        for item in self.items {
+            match item {
+                Item::Out(line) => println!("{}", line),
+                Item::Err(line) => eprintln!("{}", line),
+                Item::Record(record) => printer.print(record)?,
+            }
+        }
+        *stats += self.stats;
+        Ok(())
+    }
 }
 
-fn lex_file(stats: &mut Stats, args: &Args, path: &Path) -> Result<()> {
-    use std::io::Read;
+/// Lex file without reading it into memory at once unless it should be decoded
+fn lex_file(args: &Args, path: &Path) -> Result<Output> {
+    let mut output = Output::default();
+    let dialect = Dialect::default();
 
-    if args.print_files {
-        println!("@@ {}", path.display());
+    if args.walk.encoding.is_some() {
+        let src = args.walk.read(path)?;
+        lex_source(args, &mut output, path, &src, dialect)?;
+        return Ok(output);
     }
 
-    let mut file = File::open(&path)?;
-    let mut src = String::default();
+    let mut lexer = StreamLexer::with_dialect(BufReader::new(File::open(path)?), dialect);
+
+    begin_source(args, &mut output, path);
+    for lexeme in lexer.by_ref() {
+        match lexeme {
+            Ok(lexeme) => lex_lexeme(args, &mut output, path, &lexeme.as_lexeme(), dialect)?,
+            Err(error) if error.kind() == ErrorKind::InvalidData => {
+                return lex_bytes(args, path, dialect)
+            }
+            Err(error) => return Err(error.into()),
+        }
+    }
+    end_source(&mut output, path, lexer.diagnostics(), None);
+
+    Ok(output)
+}
+
+/// Lex source of unknown encoding keeping bytes of invalid UTF-8 opaque
+fn lex_bytes(args: &Args, path: &Path, dialect: Dialect) -> Result<Output> {
+    let mut output = Output::default();
+    let bytes = std::fs::read(path)?;
+    let source = ByteSource::from(&bytes[..]);
+    let mut lexer = source.lexer_with_dialect(dialect);
+
+    begin_source(args, &mut output, path);
+    for lexeme in lexer.by_ref() {
+        let text = lexeme.text_lossy();
+        let lexeme = Lexeme {
+            token: lexeme.token,
+            location: lexeme.location,
+            span: lexeme.span,
+            slice: &text,
+        };
+        lex_lexeme(args, &mut output, path, &lexeme, dialect)?;
+    }
+    end_source(&mut output, path, lexer.diagnostics(), Some(source.text()));
+
+    Ok(output)
+}
+
+/// Lex translation unit as compiler sees it
+fn lex_command(args: &Args, command: &compdb::Command) -> Result<Output> {
+    let mut output = Output::default();
+    let flags = command.flags();
+    let dialect = flags.dialect_or_default();
+
+    let predefines = flags.predefines();
+    if !predefines.is_empty() {
+        lex_source(
+            args,
+            &mut output,
+            Path::new("<command line>"),
+            &predefines,
+            dialect,
+        )?;
+    }
+
+    for path in flags.includes.iter().chain(Some(&command.path())) {
+        let src = args
+            .walk
+            .read(path)
+            .map_err(|error| anyhow!("Unable to read {}: {}", path.display(), error))?;
+        lex_source(args, &mut output, path, &src, dialect)?;
+    }
+
+    Ok(output)
+}
+
+fn lex_source(
+    args: &Args,
+    output: &mut Output,
+    path: &Path,
+    src: &str,
+    dialect: Dialect,
+) -> Result<()> {
+    let mut lexer = Lexer::with_dialect(src, dialect);
+
+    begin_source(args, output, path);
+    for lexeme in lexer.by_ref() {
+        lex_lexeme(args, output, path, &lexeme, dialect)?;
+    }
+    end_source(output, path, lexer.diagnostics(), Some(src));
+
+    Ok(())
+}
+
+fn begin_source(args: &Args, output: &mut Output, path: &Path) {
+    if args.print_files && args.format == Format::Table {
+        output.out(format!("@@ {}", path.display()));
+    }
+}
+
+/// Print diagnostics (with source slices when source is available) and count file
+fn end_source(output: &mut Output, path: &Path, diagnostics: &[Diagnostic], src: Option<&str>) {
+    for diagnostic in diagnostics {
+        match src {
+            Some(src) => output.err(format!(
+                "  !! {} {:?} {:?} ({})",
+                diagnostic,
+                diagnostic.span,
+                &src[diagnostic.span.clone()],
+                path.display()
+            )),
+            None => output.err(format!(
+                "  !! {} {:?} ({})",
+                diagnostic,
+                diagnostic.span,
+                path.display()
+            )),
+        }
+    }
 
-    file.read_to_string(&mut src)?;
+    output.stats.files += 1;
+}
 
-    fn print_extracted(args: &Args, name: &str, data: impl std::fmt::Debug) {
+fn lex_lexeme(
+    args: &Args,
+    output: &mut Output,
+    path: &Path,
+    lexeme: &Lexeme,
+    dialect: Dialect,
+) -> Result<()> {
+    fn print_extracted(output: &mut Output, args: &Args, name: &str, data: impl std::fmt::Debug) {
         if args.print_extracted {
-            println!("    >> {}: {:?}", name, data);
+            output.out(format!("    >> {}: {:?}", name, data));
         }
     }
 
-    fn print_failed<'l>(path: &Path, name: &str, lexeme: &Lexeme<'l>) {
-        eprintln!(
+    fn print_failed(output: &mut Output, path: &Path, name: &str, lexeme: &Lexeme) {
+        output.err(format!(
             "    !! {}: {:?} {:?} ({})",
             name,
             lexeme.span,
             lexeme.slice,
             path.display()
-        );
+        ));
     }
 
-    let lexer = Lexer::from(src.as_ref());
-
-    for lexeme in lexer {
-        if lexeme.token == Token::Unknown {
-            eprintln!(
-                "  ?? {:?} {:?} ({})",
-                lexeme.span,
-                lexeme.slice,
-                path.display()
-            );
-        } else {
-            if args.print_tokens {
-                println!(
-                    "  -- {:?} {:?} {:?}",
-                    lexeme.token, lexeme.span, lexeme.slice
-                );
-            }
+    if args.format != Format::Table {
+        output.items.push(Item::Record(lexeme_record(
+            args.format,
+            path,
+            lexeme,
+            dialect,
+        )?));
+    } else if lexeme.token == Token::Unknown {
+        output.err(format!(
+            "  ?? {:?} {:?} ({})",
+            lexeme.span,
+            lexeme.slice,
+            path.display()
+        ));
+    } else {
+        if args.print_tokens {
+            output.out(format!(
+                "  -- {:?} {:?} {:?}",
+                lexeme.token, lexeme.span, lexeme.slice
+            ));
+        }
 
-            macro_rules! extract_data {
-                (($path:ident, $args:ident, $lexeme:ident) { $($token:ident, $name:ident $(::<$type:ident>)*, $arg:ident;)* }) => {
-                    match $lexeme.token {
-                        $(
-                            Token::$token => {
-                                    if args.$arg {
-                                        if let Some(val) = lexeme.$name$(::<$type>)*() {
-                                            print_extracted(args, stringify!($name), val);
-                                        } else {
-                                            print_failed($path, stringify!($name), &lexeme);
-                                        }
-                                    }
+        macro_rules! extract_data {
+            (($output:ident, $path:ident, $args:ident, $lexeme:ident) { $($token:ident, $name:ident => $value:expr, $arg:ident;)* }) => {
+                match $lexeme.token {
+                    $(
+                        Token::$token if $args.$arg => {
+                            if let Some(val) = $value {
+                                print_extracted($output, $args, stringify!($name), val);
+                            } else {
+                                print_failed($output, $path, stringify!($name), $lexeme);
                             }
-                        )*
-                            _ => {},
-                    }
-                };
-            }
-
-            extract_data! {
-                (path, args, lexeme) {
-                    Identifier, keyword, extract_keywords;
-                    Comment, comment, extract_comments;
-                    Char, char, extract_chars;
-                    String, string, extract_strings;
-                    Int, int::<i128>, extract_ints;
-                    Float, float::<f64>, extract_floats;
+                        }
+                    )*
+                        _ => {},
                 }
+            };
+        }
+
+        extract_data! {
+            (output, path, args, lexeme) {
+                Identifier, keyword => lexeme.keyword_in(dialect), extract_keywords;
+                Comment, comment => lexeme.comment(), extract_comments;
+                Char, char => lexeme.char(), extract_chars;
+                String, string => lexeme.string(), extract_strings;
+                Int, int => lexeme.int::<i128>(), extract_ints;
+                Float, float => lexeme.float::<f64>(), extract_floats;
             }
         }
     }
 
-    stats.files += 1;
-
     Ok(())
 }
 
-fn lex_dir(stats: &mut Stats, args: &Args, path: &Path) -> Result<()> {
-    if args.print_dirs {
-        println!("** {}", path.display());
+fn lex_entry(args: &Args, entry: &Entry) -> Result<Output> {
+    match entry {
+        Entry::File(path) => lex_file(args, path),
+        Entry::Dir(path) => {
+            let mut output = Output::default();
+            if args.print_dirs && args.format == Format::Table {
+                output.out(format!("** {}", path.display()));
+            }
+            output.stats.dirs += 1;
+            Ok(output)
+        }
     }
+}
 
-    for entry in path.read_dir()? {
-        let path = entry?.path();
-        if path.is_file() {
-            if is_c(&path) {
-                lex_file(stats, args, &path)?;
-            }
-        } else if path.is_dir() {
-            lex_dir(stats, args, &path)?;
+/// Run jobs using thread pool
+///
+/// Outputs of jobs are emitted as soon as ready or in order of jobs when deterministic. Remaining
+/// jobs are not started after the first failure. In deterministic mode outputs of jobs finished
+/// ahead of earlier ones are buffered, so memory grows with outputs completed out of order.
+fn run_jobs<T: Sync>(
+    args: &Args,
+    jobs: &[T],
+    job: impl Fn(&T) -> Result<Output> + Sync,
+    printer: &mut Printer,
+    stats: &mut Stats,
+) -> Result<()> {
+    use rayon::prelude::*;
+    use std::{collections::BTreeMap, sync::mpsc::channel};
+
+    if args.jobs == 1 {
+        for item in jobs {
+            job(item)?.emit(printer, stats)?;
         }
+        return Ok(());
     }
 
-    stats.dirs += 1;
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(args.jobs)
+        .build()?;
+    let (sender, receiver) = channel();
+
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            pool.install(|| {
+                jobs.par_iter()
+                    .enumerate()
+                    // sending fails once receiver stopped on error
+                    .try_for_each_with(sender, |sender, (index, item)| {
+                        sender.send((index, job(item))).map_err(drop)
+                    })
+            })
+        });
+
+        let mut pending = BTreeMap::new();
+        let mut next = 0;
+
+        for (index, output) in receiver {
+            if !args.deterministic {
+                output?.emit(printer, stats)?;
+                continue;
+            }
 
-    Ok(())
+            pending.insert(index, output);
+            while let Some(output) = pending.remove(&next) {
+                output?.emit(printer, stats)?;
+                next += 1;
+            }
+        }
+
+        Ok(())
+    })
 }
 
 #[derive(structopt::StructOpt)]
@@ -155,9 +436,49 @@ struct Args {
     #[structopt(short = "d", long)]
     pub print_dirs: bool,
 
-    /// C source file or directory
+    /// Output format (debug, json, jsonl, csv)
+    #[structopt(short = "F", long, default_value = "debug")]
+    pub format: Format,
+
+    /// Number of parallel jobs (0 means number of CPUs)
+    #[structopt(short = "j", long, default_value = "1")]
+    pub jobs: usize,
+
+    /// Keep order of files when running parallel jobs
+    #[structopt(short = "D", long)]
+    pub deterministic: bool,
+
+    #[structopt(flatten)]
+    pub walk: walk::Args,
+
+    /// Lex files from compilation database (`compile_commands.json` or directory with it)
+    #[structopt(short = "B", long, conflicts_with = "source-path")]
+    pub compdb: Option<PathBuf>,
+
+    /// C source file or directory (`-` or `@file` to read list of paths)
     #[structopt()]
-    pub source_path: PathBuf,
+    pub source_path: Option<PathBuf>,
+
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(structopt::StructOpt)]
+enum Command {
+    /// Extract TODO, FIXME and other markers from comments
+    Todo(todo::Args),
+    /// Scan SPDX license identifiers and copyright notices
+    License(license::Args),
+    /// Report token, keyword, literal and line statistics
+    Stats(stats::Args),
+    /// Search token sequences or regular expressions in tokens of specific kinds
+    Grep(grep::Args),
+    /// Extract translatable messages into gettext template (.pot)
+    I18n(i18n::Args),
+    /// Generate ctags or etags symbol index
+    Tags(tags::Args),
+    /// Render syntax highlighted HTML or ANSI colored text
+    Highlight(highlight::Args),
 }
 
 impl AsRef<Args> for Args {
@@ -172,22 +493,64 @@ struct Stats {
     pub dirs: usize,
 }
 
+impl core::ops::AddAssign for Stats {
+    fn add_assign(&mut self, other: Self) {
+        self.files += other.files;
+        self.dirs += other.dirs;
+    }
+}
+
 #[paw::main]
 fn main(args: Args) -> Result<()> {
-    let mut stats = Stats::default();
-    let path = &args.source_path;
+    match &args.command {
+        Some(Command::Todo(args)) => return todo::run(args),
+        Some(Command::License(args)) => return license::run(args),
+        Some(Command::Stats(args)) => return stats::run(args),
+        Some(Command::Grep(args)) => return grep::run(args),
+        Some(Command::I18n(args)) => return i18n::run(args),
+        Some(Command::Tags(args)) => return tags::run(args),
+        Some(Command::Highlight(args)) => return highlight::run(args),
+        None => {}
+    }
 
-    if path.is_file() {
-        if is_c(path) {
-            lex_file(&mut stats, &args, path)?;
-        } else {
-            eprintln!("Not a C source: {}", path.display());
-        }
-    } else if path.is_dir() {
-        lex_dir(&mut stats, &args, path)?;
+    let mut stats = Stats::default();
+    let mut printer = Printer::new(
+        args.format,
+        &[
+            "file", "kind", "start", "end", "line", "column", "slice", "value",
+        ],
+    );
+
+    if let Some(path) = &args.compdb {
+        let commands = compdb::load(path)
+            .map_err(|error| anyhow!("Unable to load {}: {}", path.display(), error))?;
+        run_jobs(
+            &args,
+            &commands,
+            |command| lex_command(&args, command),
+            &mut printer,
+            &mut stats,
+        )?;
+    } else {
+        let path = args
+            .source_path
+            .as_ref()
+            .ok_or_else(|| anyhow!("Missing source path"))?;
+        let entries = walk::entries(&args.walk, core::slice::from_ref(path))?;
+        run_jobs(
+            &args,
+            &entries,
+            |entry| lex_entry(&args, entry),
+            &mut printer,
+            &mut stats,
+        )?;
     }
 
-    println!("** processed {} dirs and {} files", stats.dirs, stats.files);
+    if args.format == Format::Table {
+        println!("** processed {} dirs and {} files", stats.dirs, stats.files);
+    } else {
+        printer.finish()?;
+    }
 
     Ok(())
 }
@@ -0,0 +1,179 @@
+use anyhow::{anyhow, Error, Result};
+use serde::Serialize;
+use std::io::Write;
+
+/// Output format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Human-readable text (`table` or `debug`)
+    Table,
+    /// JSON array
+    Json,
+    /// JSON object per line
+    Jsonl,
+    /// Comma-separated values
+    Csv,
+}
+
+impl std::str::FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "table" | "debug" => Self::Table,
+            "json" => Self::Json,
+            "jsonl" => Self::Jsonl,
+            "csv" => Self::Csv,
+            _ => return Err(anyhow!("Unknown format: {}", s)),
+        })
+    }
+}
+
+/// Records printer
+///
+/// JSON Lines and CSV records are printed immediately, JSON array is printed incrementally,
+/// table rows are collected to align columns.
+pub struct Printer {
+    format: Format,
+    count: usize,
+    rows: Vec<Vec<String>>,
+}
+
+impl Printer {
+    /// Create printer with column names for table and CSV
+    pub fn new(format: Format, header: &[&str]) -> Self {
+        let header = header.iter().map(|name| name.to_string()).collect();
+        let mut printer = Self {
+            format,
+            count: 0,
+            rows: Vec::new(),
+        };
+        match format {
+            Format::Table => printer.rows.push(header),
+            Format::Csv => println!("{}", csv_row(&header)),
+            _ => {}
+        }
+        printer
+    }
+
+    /// Output format
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Print single record
+    ///
+    /// The `row` should return the cells in order of header columns.
+    pub fn record(
+        &mut self,
+        record: &impl Serialize,
+        row: impl FnOnce() -> Vec<String>,
+    ) -> Result<()> {
+        let record = Record::new(self.format, record, row)?;
+        self.print(record)
+    }
+
+    /// Print prepared record
+    pub fn print(&mut self, record: Record) -> Result<()> {
+        let stdout = std::io::stdout();
+        let mut out = stdout.lock();
+
+        match (self.format, record) {
+            (Format::Table, Record::Row(row)) => self.rows.push(row),
+            (Format::Csv, Record::Row(row)) => writeln!(out, "{}", csv_row(&row))?,
+            (Format::Jsonl, Record::Json(json)) => writeln!(out, "{}", json)?,
+            (Format::Json, Record::Json(json)) => {
+                out.write_all(if self.count == 0 { b"[\n" } else { b",\n" })?;
+                out.write_all(json.as_bytes())?;
+            }
+            _ => return Err(anyhow!("Record does not match output format")),
+        }
+
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Finish printing
+    pub fn finish(self) -> Result<()> {
+        match self.format {
+            Format::Table => print_table(&self.rows),
+            Format::Json => {
+                if self.count == 0 {
+                    println!("[]");
+                } else {
+                    println!("\n]");
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Record prepared for printing
+///
+/// Allows formatting records in parallel.
+pub enum Record {
+    /// Serialized JSON
+    Json(String),
+    /// Table or CSV cells
+    Row(Vec<String>),
+}
+
+impl Record {
+    /// Prepare record for format
+    pub fn new(
+        format: Format,
+        record: &impl Serialize,
+        row: impl FnOnce() -> Vec<String>,
+    ) -> Result<Self> {
+        Ok(match format {
+            Format::Table | Format::Csv => Self::Row(row()),
+            Format::Json | Format::Jsonl => Self::Json(serde_json::to_string(record)?),
+        })
+    }
+}
+
+fn csv_row(cells: &[String]) -> String {
+    cells
+        .iter()
+        .map(|cell| {
+            if cell.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Print rows as table with aligned columns
+fn print_table(rows: &[Vec<String>]) {
+    let mut widths = Vec::<usize>::new();
+    for row in rows {
+        for (index, cell) in row.iter().enumerate() {
+            let width = cell.chars().count();
+            if index < widths.len() {
+                widths[index] = widths[index].max(width);
+            } else {
+                widths.push(width);
+            }
+        }
+    }
+
+    for row in rows {
+        let mut line = String::new();
+        for (index, cell) in row.iter().enumerate() {
+            if index > 0 {
+                line.push_str("  ");
+            }
+            line.push_str(cell);
+            if index + 1 < row.len() {
+                let width = cell.chars().count();
+                line.push_str(&" ".repeat(widths[index] - width));
+            }
+        }
+        println!("{}", line.trim_end());
+    }
+}
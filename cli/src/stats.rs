@@ -0,0 +1,185 @@
+use crate::{
+    output::{Format, Printer},
+    walk::{self, sources},
+};
+use anyhow::Result;
+use clex::stats::{scan, Stats};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+#[derive(structopt::StructOpt)]
+pub struct Args {
+    /// Print only total statistics
+    #[structopt(short = "S", long)]
+    pub summary: bool,
+
+    /// Output format (table, json, jsonl, csv)
+    #[structopt(short = "F", long, default_value = "table")]
+    pub format: Format,
+
+    #[structopt(flatten)]
+    pub walk: walk::Args,
+
+    /// C source files or directories (`-` or `@file` to read list of paths)
+    #[structopt(required = true)]
+    pub paths: Vec<PathBuf>,
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    /// Source file or none for total
+    file: Option<&'a Path>,
+    lines: usize,
+    code_lines: usize,
+    comment_lines: usize,
+    blank_lines: usize,
+    comment_ratio: f64,
+    tokens: BTreeMap<String, usize>,
+    keywords: BTreeMap<&'static str, usize>,
+    literals: BTreeMap<String, usize>,
+    longest: Vec<LongLineRecord<'a>>,
+}
+
+#[derive(Clone, Serialize)]
+struct LongLineRecord<'a> {
+    file: &'a Path,
+    line: u32,
+    length: usize,
+}
+
+impl<'a> Record<'a> {
+    fn new(file: Option<&'a Path>, stats: &Stats, longest: Vec<LongLineRecord<'a>>) -> Self {
+        let literals = [("int", &stats.ints), ("float", &stats.floats)]
+            .into_iter()
+            .flat_map(|(kind, counts)| {
+                counts
+                    .iter()
+                    .map(move |(radix, count)| (format!("{}.{}", kind, radix), *count))
+            })
+            .chain(
+                [("char", &stats.chars), ("string", &stats.strings)]
+                    .into_iter()
+                    .flat_map(|(kind, counts)| {
+                        counts.iter().map(move |(encoding, count)| {
+                            (format!("{}.{}", kind, encoding), *count)
+                        })
+                    }),
+            )
+            .collect();
+
+        Self {
+            file,
+            lines: stats.lines,
+            code_lines: stats.code_lines,
+            comment_lines: stats.comment_lines,
+            blank_lines: stats.blank_lines,
+            comment_ratio: stats.comment_ratio(),
+            tokens: stats
+                .tokens
+                .iter()
+                .map(|(token, count)| (format!("{:?}", token), *count))
+                .collect(),
+            keywords: stats
+                .keywords
+                .iter()
+                .map(|(keyword, count)| (keyword.as_str(), *count))
+                .collect(),
+            literals,
+            longest,
+        }
+    }
+
+    /// Rows of file, group, name and value
+    fn rows(&self) -> Vec<Vec<String>> {
+        let file = self
+            .file
+            .map(|file| file.display().to_string())
+            .unwrap_or_else(|| "total".into());
+        let mut rows = Vec::new();
+        let mut row = |group: &str, name: &str, value: String| {
+            rows.push(vec![file.clone(), group.into(), name.into(), value]);
+        };
+
+        row("lines", "total", self.lines.to_string());
+        row("lines", "code", self.code_lines.to_string());
+        row("lines", "comment", self.comment_lines.to_string());
+        row("lines", "blank", self.blank_lines.to_string());
+        row(
+            "lines",
+            "comment/code",
+            format!("{:.2}", self.comment_ratio),
+        );
+        for (name, count) in &self.tokens {
+            row("tokens", name, count.to_string());
+        }
+        for (name, count) in &self.keywords {
+            row("keywords", name, count.to_string());
+        }
+        for (name, count) in &self.literals {
+            row("literals", name, count.to_string());
+        }
+        for long in &self.longest {
+            let name = if self.file.is_some() {
+                long.line.to_string()
+            } else {
+                format!("{}:{}", long.file.display(), long.line)
+            };
+            row("longest", &name, long.length.to_string());
+        }
+
+        rows
+    }
+}
+
+pub fn run(args: &Args) -> Result<()> {
+    let sources = sources(&args.walk, &args.paths)?;
+    let mut printer = Printer::new(args.format, &["file", "group", "name", "value"]);
+    let mut total = Stats::default();
+    let mut longest = Vec::new();
+
+    for path in &sources {
+        let src = args.walk.read(path)?;
+        let stats = scan(&src);
+
+        total += &stats;
+
+        let lines = stats
+            .longest
+            .iter()
+            .map(|long| LongLineRecord {
+                file: path,
+                line: long.line,
+                length: long.length,
+            })
+            .collect::<Vec<_>>();
+
+        if !args.summary {
+            let record = Record::new(Some(path), &stats, lines.clone());
+            print_record(&mut printer, &record)?;
+        }
+
+        longest.extend(lines);
+    }
+
+    longest.sort_by_key(|long| core::cmp::Reverse(long.length));
+    longest.truncate(Stats::LONGEST);
+
+    print_record(&mut printer, &Record::new(None, &total, longest))?;
+
+    printer.finish()
+}
+
+fn print_record(printer: &mut Printer, record: &Record) -> Result<()> {
+    match printer.format() {
+        Format::Table | Format::Csv => {
+            for row in record.rows() {
+                printer.record(&(), || row)?;
+            }
+            Ok(())
+        }
+        _ => printer.record(record, Vec::new),
+    }
+}
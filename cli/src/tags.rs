@@ -0,0 +1,46 @@
+use crate::walk::{self, sources};
+use anyhow::Result;
+use clex::tags::Index;
+use std::path::PathBuf;
+
+#[derive(structopt::StructOpt)]
+pub struct Args {
+    /// Write Emacs `TAGS` format instead of ctags
+    #[structopt(short = "e", long)]
+    pub etags: bool,
+
+    /// Output file instead of stdout
+    #[structopt(short = "o", long)]
+    pub output: Option<PathBuf>,
+
+    #[structopt(flatten)]
+    pub walk: walk::Args,
+
+    /// C source files or directories (`-` or `@file` to read list of paths)
+    #[structopt(required = true)]
+    pub paths: Vec<PathBuf>,
+}
+
+pub fn run(args: &Args) -> Result<()> {
+    let sources = sources(&args.walk, &args.paths)?;
+    let mut index = Index::default();
+
+    for path in &sources {
+        let src = args.walk.read(path)?;
+        index.add(&path.display().to_string(), &src);
+    }
+
+    let tags = if args.etags {
+        index.etags().to_string()
+    } else {
+        index.ctags().to_string()
+    };
+
+    if let Some(path) = &args.output {
+        std::fs::write(path, tags)?;
+    } else {
+        print!("{}", tags);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use crate::{
+    output::{Format, Printer},
+    walk::{self, sources},
+};
+use anyhow::{anyhow, Result};
+use clex::markers::{scan, Options, Tag};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+#[derive(structopt::StructOpt)]
+pub struct Args {
+    /// Tag patterns instead of default (`NAME`, `NAME()`, `NAME[]`, `NAME ...`, `^NAME`)
+    #[structopt(short = "T", long = "tag")]
+    pub tags: Vec<String>,
+
+    /// Output format (table, json, jsonl, csv)
+    #[structopt(short = "F", long, default_value = "table")]
+    pub format: Format,
+
+    #[structopt(flatten)]
+    pub walk: walk::Args,
+
+    /// C source files or directories (`-` or `@file` to read list of paths)
+    #[structopt(required = true)]
+    pub paths: Vec<PathBuf>,
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    file: &'a Path,
+    line: u32,
+    column: u32,
+    tag: String,
+    owner: Option<String>,
+    message: String,
+}
+
+pub fn run(args: &Args) -> Result<()> {
+    let mut options = Options::default();
+
+    if !args.tags.is_empty() {
+        options.tags = args
+            .tags
+            .iter()
+            .map(|tag| {
+                tag.parse::<Tag>()
+                    .map_err(|_| anyhow!("Invalid tag pattern: {:?}", tag))
+            })
+            .collect::<Result<_>>()?;
+    }
+
+    let sources = sources(&args.walk, &args.paths)?;
+    let mut printer = Printer::new(
+        args.format,
+        &["file", "line", "column", "tag", "owner", "message"],
+    );
+
+    for path in &sources {
+        let src = args.walk.read(path)?;
+
+        for marker in scan(&src, &options) {
+            let record = Record {
+                file: path,
+                line: marker.location.line,
+                column: marker.location.column,
+                tag: marker.tag,
+                owner: marker.owner,
+                message: marker.message,
+            };
+            printer.record(&record, || {
+                vec![
+                    record.file.display().to_string(),
+                    record.line.to_string(),
+                    record.column.to_string(),
+                    record.tag.clone(),
+                    record.owner.clone().unwrap_or_default(),
+                    record.message.clone(),
+                ]
+            })?;
+        }
+    }
+
+    printer.finish()
+}
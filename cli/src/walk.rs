@@ -0,0 +1,219 @@
+use anyhow::{anyhow, Result};
+use clex::{
+    encoding::{self, Encoding},
+    ByteSource,
+};
+use ignore::{
+    overrides::{Override, OverrideBuilder},
+    WalkBuilder,
+};
+use std::{
+    io::BufRead,
+    path::{Path, PathBuf},
+};
+
+// Source files selection options
+//
+// Not a doc comment: structopt would use it as about text of every command flattening them.
+#[derive(structopt::StructOpt)]
+pub struct Args {
+    /// Select only files matching glob pattern (overrides extensions)
+    #[structopt(long = "include", number_of_values = 1)]
+    pub include: Vec<String>,
+
+    /// Skip files and directories matching glob pattern
+    #[structopt(long = "exclude", number_of_values = 1)]
+    pub exclude: Vec<String>,
+
+    /// Source file extensions
+    #[structopt(long = "ext", default_value = "c,h", require_delimiter = true)]
+    pub extensions: Vec<String>,
+
+    /// Do not respect .gitignore and .ignore files
+    #[structopt(long)]
+    pub no_ignore: bool,
+
+    /// Walk into hidden files and directories
+    #[structopt(long)]
+    pub hidden: bool,
+
+    /// Follow symbolic links to directories
+    #[structopt(short = "L", long)]
+    pub follow: bool,
+
+    /// Encoding of non-UTF-8 sources (utf-8, latin1, windows-1252, utf-16le, utf-16be)
+    #[structopt(long, parse(try_from_str = parse_encoding))]
+    pub encoding: Option<Encoding>,
+}
+
+fn parse_encoding(name: &str) -> Result<Encoding> {
+    name.parse()
+        .map_err(|_| anyhow!("Unknown encoding: {}", name))
+}
+
+impl Args {
+    /// Read source file decoding it when encoding is specified
+    ///
+    /// Byte order mark takes precedence over specified encoding. Without encoding bytes of
+    /// invalid UTF-8 are kept opaque (see `ByteSource`).
+    pub fn read(&self, path: &Path) -> Result<String> {
+        let bytes = std::fs::read(path)?;
+        Ok(match self.encoding {
+            Some(fallback) => encoding::decode(&bytes, fallback).into_owned(),
+            None => match String::from_utf8(bytes) {
+                Ok(text) => text,
+                Err(error) => ByteSource::from(error.as_bytes()).text().to_owned(),
+            },
+        })
+    }
+
+    fn has_extension(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| {
+                self.extensions
+                    .iter()
+                    .any(|known| known.trim_start_matches('.') == ext)
+            })
+            .unwrap_or(false)
+    }
+
+    fn overrides(&self, root: &Path) -> Result<Override> {
+        let mut builder = OverrideBuilder::new(root);
+        for glob in &self.include {
+            builder.add(glob)?;
+        }
+        for glob in &self.exclude {
+            builder.add(&format!("!{}", glob))?;
+        }
+        Ok(builder.build()?)
+    }
+
+    fn is_selected(&self, overrides: &Override, path: &Path) -> bool {
+        !overrides.matched(path, false).is_ignore()
+            && (!self.include.is_empty() || self.has_extension(path))
+    }
+}
+
+/// Walked entry
+pub enum Entry {
+    /// Directory (before its contents)
+    Dir(PathBuf),
+    /// C source file
+    File(PathBuf),
+}
+
+/// Collect C sources from files and directories
+pub fn sources(args: &Args, paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    Ok(entries(args, paths)?
+        .into_iter()
+        .filter_map(|entry| match entry {
+            Entry::File(path) => Some(path),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Collect directories and C sources in sorted order
+///
+/// The path `-` means list of paths from stdin and `@file` means list of paths from file.
+pub fn entries(args: &Args, paths: &[PathBuf]) -> Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    let overrides = args.overrides(Path::new("."))?;
+
+    for path in paths {
+        if let Some(list) = list_source(path) {
+            for path in read_list(list)? {
+                if path.is_dir() {
+                    collect_dir(args, &mut entries, &path)?;
+                } else if args.is_selected(&overrides, &path) {
+                    entries.push(Entry::File(path));
+                }
+            }
+        } else if path.is_dir() {
+            collect_dir(args, &mut entries, path)?;
+        } else if args.is_selected(&overrides, path) {
+            entries.push(Entry::File(path.clone()));
+        } else {
+            eprintln!("Not a C source: {}", path.display());
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Path list source
+enum List<'a> {
+    Stdin,
+    File(&'a Path),
+}
+
+fn list_source(path: &Path) -> Option<List<'_>> {
+    let path = path.to_str()?;
+    if path == "-" {
+        Some(List::Stdin)
+    } else {
+        path.strip_prefix('@')
+            .map(|path| List::File(Path::new(path)))
+    }
+}
+
+fn read_list(list: List) -> Result<Vec<PathBuf>> {
+    let lines = match list {
+        List::Stdin => std::io::stdin()
+            .lock()
+            .lines()
+            .collect::<Result<Vec<_>, _>>()?,
+        List::File(path) => std::fs::read_to_string(path)
+            .map_err(|error| anyhow!("Unable to read {}: {}", path.display(), error))?
+            .lines()
+            .map(String::from)
+            .collect(),
+    };
+
+    Ok(lines
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(PathBuf::from)
+        .collect())
+}
+
+fn collect_dir(args: &Args, entries: &mut Vec<Entry>, path: &Path) -> Result<()> {
+    let overrides = args.overrides(path)?;
+    let walker = WalkBuilder::new(path)
+        .hidden(!args.hidden)
+        .parents(!args.no_ignore)
+        .ignore(!args.no_ignore)
+        .git_ignore(!args.no_ignore)
+        .git_global(!args.no_ignore)
+        .git_exclude(!args.no_ignore)
+        .require_git(false)
+        .follow_links(args.follow)
+        .overrides(overrides.clone())
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .build();
+
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) => {
+                eprintln!("Unable to walk: {}", error);
+                continue;
+            }
+        };
+        let path = entry.path();
+
+        if entry
+            .file_type()
+            .map(|type_| type_.is_dir())
+            .unwrap_or(false)
+        {
+            entries.push(Entry::Dir(path.into()));
+        } else if path.is_file() && args.is_selected(&overrides, path) {
+            entries.push(Entry::File(path.into()));
+        }
+    }
+
+    Ok(())
+}
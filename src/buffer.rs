@@ -0,0 +1,487 @@
+use crate::{Diagnostic, Dialect, Lexeme, Lexer, Location, Token};
+use core::ops::Range;
+use std::sync::Arc;
+
+/// Lexeme flags
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct TokenFlags(u8);
+
+impl TokenFlags {
+    /// First lexeme on line
+    pub const LINE_START: Self = Self(1);
+    /// Lexeme is preceded by whitespace
+    pub const SPACE_BEFORE: Self = Self(2);
+
+    /// All flags of other are set
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Flags are not set
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl core::ops::BitOr for TokenFlags {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+}
+
+impl core::ops::BitOrAssign for TokenFlags {
+    fn bitor_assign(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+}
+
+/// Memory used by token buffer in bytes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// Source code (shared with other owners)
+    pub source: usize,
+    /// Token kinds, spans and flags
+    pub tokens: usize,
+    /// Line table
+    pub lines: usize,
+    /// Diagnostics
+    pub diagnostics: usize,
+}
+
+impl MemoryUsage {
+    /// Total memory usage
+    pub fn total(&self) -> usize {
+        self.source + self.tokens + self.lines + self.diagnostics
+    }
+}
+
+/// Lexed source code which owns its source
+///
+/// Source code is shared using [`Arc`] while lexemes are stored as struct of arrays of token
+/// kinds, spans and flags. Locations are computed using table of line starts, so buffer takes
+/// about 10 bytes per lexeme and 4 bytes per line besides source code.
+#[derive(Debug, Clone)]
+pub struct TokenBuffer {
+    source: Arc<str>,
+    dialect: Dialect,
+    tokens: Vec<Token>,
+    starts: Vec<u32>,
+    ends: Vec<u32>,
+    flags: Vec<TokenFlags>,
+    /// Offsets of line starts
+    lines: Vec<u32>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl From<Arc<str>> for TokenBuffer {
+    fn from(source: Arc<str>) -> Self {
+        Self::with_dialect(source, Dialect::default())
+    }
+}
+
+impl From<String> for TokenBuffer {
+    fn from(source: String) -> Self {
+        Self::from(Arc::<str>::from(source))
+    }
+}
+
+impl From<&str> for TokenBuffer {
+    fn from(source: &str) -> Self {
+        Self::from(Arc::<str>::from(source))
+    }
+}
+
+impl TokenBuffer {
+    /// Lex source code of specific dialect
+    ///
+    /// # Panics
+    ///
+    /// Panics if source is larger than 4 GiB.
+    pub fn with_dialect(source: impl Into<Arc<str>>, dialect: Dialect) -> Self {
+        let source = source.into();
+        assert!(
+            u32::try_from(source.len()).is_ok(),
+            "Source is too large for token buffer"
+        );
+
+        let lines = core::iter::once(0)
+            .chain(source.match_indices('\n').map(|(pos, _)| pos as u32 + 1))
+            .collect();
+
+        let mut buffer = Self {
+            source: source.clone(),
+            dialect,
+            tokens: Vec::new(),
+            starts: Vec::new(),
+            ends: Vec::new(),
+            flags: Vec::new(),
+            lines,
+            diagnostics: Vec::new(),
+        };
+
+        let mut lexer = Lexer::with_dialect(&source, dialect);
+        let mut end = 0;
+
+        for lexeme in lexer.by_ref() {
+            let gap = &source[end..lexeme.span.start];
+            let mut flags = TokenFlags::default();
+            if buffer.tokens.is_empty() || gap.contains('\n') {
+                flags |= TokenFlags::LINE_START;
+            }
+            if !gap.is_empty() {
+                flags |= TokenFlags::SPACE_BEFORE;
+            }
+            end = lexeme.span.end;
+
+            buffer.tokens.push(lexeme.token);
+            buffer.starts.push(lexeme.span.start as u32);
+            buffer.ends.push(lexeme.span.end as u32);
+            buffer.flags.push(flags);
+        }
+
+        buffer.diagnostics = lexer.take_diagnostics();
+        buffer
+    }
+
+    /// Source code
+    pub fn source(&self) -> &Arc<str> {
+        &self.source
+    }
+
+    /// Language dialect
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    /// Diagnostics reported by lexer
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Number of lexemes
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Source has no lexemes
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Token kind at index
+    pub fn token(&self, index: usize) -> Option<Token> {
+        self.tokens.get(index).copied()
+    }
+
+    /// Byte range of lexeme at index
+    pub fn span(&self, index: usize) -> Option<Range<usize>> {
+        Some(*self.starts.get(index)? as usize..self.ends[index] as usize)
+    }
+
+    /// Flags of lexeme at index
+    pub fn flags(&self, index: usize) -> Option<TokenFlags> {
+        self.flags.get(index).copied()
+    }
+
+    /// Location of byte position
+    pub fn location(&self, point: usize) -> Location {
+        let line = self.lines.partition_point(|&start| start as usize <= point) - 1;
+        Location {
+            point,
+            line: line as u32 + 1,
+            column: (point - self.lines[line] as usize) as u32 + 1,
+        }
+    }
+
+    /// Lexeme at index
+    pub fn get(&self, index: usize) -> Option<Lexeme<'_>> {
+        let span = self.span(index)?;
+        Some(Lexeme {
+            token: self.tokens[index],
+            location: self.location(span.start),
+            slice: &self.source[span.clone()],
+            span,
+        })
+    }
+
+    /// Iterate over lexemes
+    pub fn iter(&self) -> TokenIter<'_> {
+        self.slice(0..self.len()).iter()
+    }
+
+    /// Range of lexemes
+    ///
+    /// # Panics
+    ///
+    /// Panics if range is out of bounds.
+    pub fn slice(&self, range: Range<usize>) -> TokenSlice<'_> {
+        assert!(
+            range.start <= range.end && range.end <= self.len(),
+            "Token range {:?} is out of bounds",
+            range
+        );
+        TokenSlice {
+            buffer: self,
+            range,
+        }
+    }
+
+    /// Memory used by buffer
+    pub fn memory_usage(&self) -> MemoryUsage {
+        use core::mem::size_of;
+
+        MemoryUsage {
+            source: self.source.len(),
+            tokens: self.tokens.capacity() * size_of::<Token>()
+                + (self.starts.capacity() + self.ends.capacity()) * size_of::<u32>()
+                + self.flags.capacity() * size_of::<TokenFlags>(),
+            lines: self.lines.capacity() * size_of::<u32>(),
+            diagnostics: self.diagnostics.capacity() * size_of::<Diagnostic>(),
+        }
+    }
+
+    /// Release unused capacity
+    pub fn shrink_to_fit(&mut self) {
+        self.tokens.shrink_to_fit();
+        self.starts.shrink_to_fit();
+        self.ends.shrink_to_fit();
+        self.flags.shrink_to_fit();
+        self.lines.shrink_to_fit();
+        self.diagnostics.shrink_to_fit();
+    }
+}
+
+impl<'b> IntoIterator for &'b TokenBuffer {
+    type Item = Lexeme<'b>;
+    type IntoIter = TokenIter<'b>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Range of lexemes in token buffer
+#[derive(Debug, Clone)]
+pub struct TokenSlice<'b> {
+    buffer: &'b TokenBuffer,
+    range: Range<usize>,
+}
+
+impl<'b> TokenSlice<'b> {
+    /// Range of lexeme indexes in buffer
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Number of lexemes
+    pub fn len(&self) -> usize {
+        self.range.len()
+    }
+
+    /// Slice has no lexemes
+    pub fn is_empty(&self) -> bool {
+        self.range.is_empty()
+    }
+
+    /// Lexeme at index relative to slice
+    pub fn get(&self, index: usize) -> Option<Lexeme<'b>> {
+        if index < self.len() {
+            self.buffer.get(self.range.start + index)
+        } else {
+            None
+        }
+    }
+
+    /// Sub-range of lexemes relative to slice
+    ///
+    /// # Panics
+    ///
+    /// Panics if range is out of bounds.
+    pub fn slice(&self, range: Range<usize>) -> Self {
+        assert!(
+            range.start <= range.end && range.end <= self.len(),
+            "Token range {:?} is out of bounds",
+            range
+        );
+        Self {
+            buffer: self.buffer,
+            range: self.range.start + range.start..self.range.start + range.end,
+        }
+    }
+
+    /// Byte range of source covered by lexemes
+    pub fn span(&self) -> Range<usize> {
+        match (self.range.start, self.range.end.checked_sub(1)) {
+            (first, Some(last)) if first <= last => {
+                self.buffer.starts[first] as usize..self.buffer.ends[last] as usize
+            }
+            _ => {
+                let point = self
+                    .buffer
+                    .starts
+                    .get(self.range.start)
+                    .map(|&start| start as usize)
+                    .unwrap_or(self.buffer.source.len());
+                point..point
+            }
+        }
+    }
+
+    /// Source code covered by lexemes
+    pub fn text(&self) -> &'b str {
+        &self.buffer.source[self.span()]
+    }
+
+    /// Iterate over lexemes
+    pub fn iter(&self) -> TokenIter<'b> {
+        TokenIter {
+            buffer: self.buffer,
+            range: self.range.clone(),
+        }
+    }
+}
+
+impl<'b> IntoIterator for TokenSlice<'b> {
+    type Item = Lexeme<'b>;
+    type IntoIter = TokenIter<'b>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over lexemes of token buffer
+#[derive(Debug, Clone)]
+pub struct TokenIter<'b> {
+    buffer: &'b TokenBuffer,
+    range: Range<usize>,
+}
+
+impl<'b> Iterator for TokenIter<'b> {
+    type Item = Lexeme<'b>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.range
+            .next()
+            .map(|index| self.buffer.get(index).unwrap())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.range
+            .nth(n)
+            .map(|index| self.buffer.get(index).unwrap())
+    }
+}
+
+impl<'b> DoubleEndedIterator for TokenIter<'b> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.range
+            .next_back()
+            .map(|index| self.buffer.get(index).unwrap())
+    }
+}
+
+impl<'b> ExactSizeIterator for TokenIter<'b> {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SOURCE: &str = "int a; /* c\n */ b\n  = a+1;";
+
+    fn tuples<'b>(
+        lexemes: impl Iterator<Item = Lexeme<'b>>,
+    ) -> Vec<(Token, Range<usize>, Location, &'b str)> {
+        lexemes
+            .map(|lexeme| (lexeme.token, lexeme.span, lexeme.location, lexeme.slice))
+            .collect()
+    }
+
+    #[test]
+    fn lexemes() {
+        let buffer = TokenBuffer::from(SOURCE);
+        assert_eq!(tuples(buffer.iter()), tuples(Lexer::from(SOURCE)));
+        assert_eq!(
+            tuples(buffer.iter().rev().skip(2).take(1)),
+            tuples(Lexer::from(SOURCE).skip(7).take(1))
+        );
+        assert_eq!(buffer.iter().len(), 10);
+        assert_eq!(buffer.get(5).unwrap().location.to_string(), "3:3");
+        assert!(buffer.get(10).is_none());
+    }
+
+    #[test]
+    fn flags() {
+        let buffer = TokenBuffer::from(SOURCE);
+        let flags = (0..buffer.len())
+            .map(|index| {
+                let flags = buffer.flags(index).unwrap();
+                (
+                    flags.contains(TokenFlags::LINE_START),
+                    flags.contains(TokenFlags::SPACE_BEFORE),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            flags,
+            [
+                (true, false),
+                (false, true),
+                (false, false),
+                (false, true),
+                (false, true),
+                (true, true),
+                (false, true),
+                (false, false),
+                (false, false),
+                (false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn slices() {
+        let buffer = TokenBuffer::from(SOURCE);
+        let slice = buffer.slice(3..8);
+        assert_eq!(slice.text(), "/* c\n */ b\n  = a+");
+        let inner = slice.slice(2..4);
+        assert_eq!(inner.range(), 5..7);
+        assert_eq!(
+            inner.iter().map(|lexeme| lexeme.slice).collect::<Vec<_>>(),
+            ["=", "a"]
+        );
+        assert_eq!(inner.get(1).unwrap().location.to_string(), "3:5");
+        assert!(inner.get(2).is_none());
+        assert_eq!(buffer.slice(2..2).span(), 5..5);
+        assert_eq!(buffer.slice(10..10).span(), 26..26);
+    }
+
+    #[test]
+    fn shared() {
+        let buffer = Arc::new(TokenBuffer::from(String::from(SOURCE)));
+        let thread = {
+            let buffer = buffer.clone();
+            std::thread::spawn(move || {
+                buffer
+                    .iter()
+                    .filter(|lexeme| lexeme.token == Token::Identifier)
+                    .count()
+            })
+        };
+        assert_eq!(thread.join().unwrap(), 4);
+
+        let mut buffer = TokenBuffer::from(SOURCE);
+        buffer.shrink_to_fit();
+        let usage = buffer.memory_usage();
+        assert_eq!(usage.source, SOURCE.len());
+        assert_eq!(usage.tokens, 10 * 10);
+        assert_eq!(usage.lines, 3 * 4);
+        assert_eq!(usage.total(), SOURCE.len() + 100 + 12);
+    }
+}
@@ -0,0 +1,207 @@
+use crate::{Diagnostic, Dialect, Lexeme, Lexer, Location, Token};
+use std::borrow::Cow;
+
+/// Byte which replaces invalid UTF-8 while lexing (never part of identifiers or symbols)
+const OPAQUE: char = '\x7f';
+
+/// Source code in bytes of unknown encoding
+///
+/// Bytes of invalid UTF-8 sequences are treated as opaque: they are kept inside comments and
+/// literals and become unknown tokens elsewhere. Offsets and locations refer to original bytes.
+pub struct ByteSource<'l> {
+    bytes: &'l [u8],
+    /// Text with opaque bytes replaced
+    text: Cow<'l, str>,
+}
+
+impl<'l> From<&'l [u8]> for ByteSource<'l> {
+    fn from(bytes: &'l [u8]) -> Self {
+        Self {
+            bytes,
+            text: mask(bytes),
+        }
+    }
+}
+
+impl<'l> ByteSource<'l> {
+    /// Source bytes
+    pub fn bytes(&self) -> &'l [u8] {
+        self.bytes
+    }
+
+    /// Text with bytes of invalid UTF-8 replaced by `\x7f` (offsets are kept)
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Source is valid UTF-8
+    pub fn is_utf8(&self) -> bool {
+        matches!(self.text, Cow::Borrowed(_))
+    }
+
+    /// Create lexer
+    pub fn lexer(&self) -> ByteLexer<'_> {
+        self.lexer_with_dialect(Dialect::default())
+    }
+
+    /// Create lexer for specific dialect
+    pub fn lexer_with_dialect(&self, dialect: Dialect) -> ByteLexer<'_> {
+        ByteLexer {
+            bytes: self.bytes,
+            inner: Lexer::with_dialect(&self.text, dialect),
+        }
+    }
+}
+
+/// Replace bytes of invalid UTF-8 sequences with opaque character
+fn mask(mut bytes: &[u8]) -> Cow<'_, str> {
+    let mut text = match core::str::from_utf8(bytes) {
+        Ok(text) => return Cow::Borrowed(text),
+        Err(_) => String::with_capacity(bytes.len()),
+    };
+
+    loop {
+        match core::str::from_utf8(bytes) {
+            Ok(valid) => {
+                text.push_str(valid);
+                break;
+            }
+            Err(error) => {
+                let (valid, rest) = bytes.split_at(error.valid_up_to());
+                text.push_str(core::str::from_utf8(valid).unwrap());
+                let invalid = error.error_len().unwrap_or(rest.len());
+                text.extend(core::iter::repeat_n(OPAQUE, invalid));
+                bytes = &rest[invalid..];
+            }
+        }
+    }
+
+    Cow::Owned(text)
+}
+
+/// C lexeme in bytes
+#[derive(Debug, Clone)]
+pub struct ByteLexeme<'l> {
+    /// Token kind
+    pub token: Token,
+    /// Position in source code
+    pub location: Location,
+    /// Byte range in source code
+    pub span: core::ops::Range<usize>,
+    /// Byte slice
+    pub slice: &'l [u8],
+}
+
+impl<'l> ByteLexeme<'l> {
+    /// Slice when it is valid UTF-8
+    pub fn text(&self) -> Option<&'l str> {
+        core::str::from_utf8(self.slice).ok()
+    }
+
+    /// Slice with invalid UTF-8 replaced
+    pub fn text_lossy(&self) -> Cow<'l, str> {
+        String::from_utf8_lossy(self.slice)
+    }
+
+    /// Convert into lexeme to extract values when slice is valid UTF-8
+    pub fn lexeme(&self) -> Option<Lexeme<'l>> {
+        self.text().map(|slice| Lexeme {
+            token: self.token,
+            location: self.location,
+            span: self.span.clone(),
+            slice,
+        })
+    }
+}
+
+/// C lexer over bytes
+///
+/// Created by [`ByteSource::lexer`].
+pub struct ByteLexer<'l> {
+    bytes: &'l [u8],
+    inner: Lexer<'l>,
+}
+
+impl<'l> ByteLexer<'l> {
+    /// Source bytes
+    pub fn bytes(&self) -> &'l [u8] {
+        self.bytes
+    }
+
+    /// Language dialect
+    pub fn dialect(&self) -> Dialect {
+        self.inner.dialect()
+    }
+
+    /// Diagnostics reported so far
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        self.inner.diagnostics()
+    }
+
+    /// Take diagnostics reported so far
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        self.inner.take_diagnostics()
+    }
+}
+
+impl<'l> Iterator for ByteLexer<'l> {
+    type Item = ByteLexeme<'l>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|lexeme| ByteLexeme {
+            token: lexeme.token,
+            location: lexeme.location,
+            slice: &self.bytes[lexeme.span.clone()],
+            span: lexeme.span,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn lex(bytes: &[u8]) -> Vec<(Token, Vec<u8>, u32)> {
+        ByteSource::from(bytes)
+            .lexer()
+            .map(|lexeme| (lexeme.token, lexeme.slice.into(), lexeme.location.column))
+            .collect()
+    }
+
+    #[test]
+    fn opaque() {
+        assert_eq!(
+            lex(b"/* caf\xe9 */ s = \"\x93q\x94\" \xff;"),
+            [
+                (Token::Comment, b"/* caf\xe9 */".to_vec(), 1),
+                (Token::Identifier, b"s".to_vec(), 12),
+                (Token::Symbol, b"=".to_vec(), 14),
+                (Token::String, b"\"\x93q\x94\" ".to_vec(), 16),
+                (Token::Unknown, b"\xff".to_vec(), 22),
+                (Token::Symbol, b";".to_vec(), 23),
+            ]
+        );
+        // truncated sequence at end
+        assert_eq!(
+            lex(b"'\xe2\x82"),
+            [(Token::Unknown, b"'\xe2\x82".to_vec(), 1)]
+        );
+    }
+
+    #[test]
+    fn utf8() {
+        let source = ByteSource::from("/* é */ 'x'".as_bytes());
+        assert!(source.is_utf8());
+        let lexemes = source.lexer().collect::<Vec<_>>();
+        assert_eq!(lexemes[0].text(), Some("/* é */"));
+        assert_eq!(lexemes[1].lexeme().unwrap().char(), Some('x'));
+
+        let source = ByteSource::from(&b"// \xe9\nint"[..]);
+        assert!(!source.is_utf8());
+        assert_eq!(source.text(), "// \x7f\nint");
+        let lexemes = source.lexer().collect::<Vec<_>>();
+        assert!(lexemes[0].lexeme().is_none());
+        assert_eq!(lexemes[0].text_lossy(), "// \u{FFFD}");
+        assert_eq!(lexemes[1].location.to_string(), "2:1");
+    }
+}
@@ -1,26 +1,346 @@
+//! Comment text extraction
+
+use crate::{Lexer, Token};
+use core::ops::Range;
+
+/// Comment extraction options
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Options {
+    /// Strip leading star gutter (` * `)
+    pub strip_gutter: bool,
+    /// Strip right borders and rulers of boxed comments
+    pub strip_border: bool,
+    /// Remove common indentation
+    pub dedent: bool,
+    /// Merge consecutive line comments into single block
+    pub merge_lines: bool,
+    /// Join lines of paragraphs
+    pub reflow: bool,
+}
+
+impl Options {
+    /// Options which gives clean text
+    pub fn clean() -> Self {
+        Self {
+            strip_gutter: true,
+            strip_border: true,
+            dedent: true,
+            merge_lines: true,
+            reflow: false,
+        }
+    }
+
+    fn normalize(&self) -> bool {
+        self.strip_gutter || self.strip_border || self.dedent || self.reflow
+    }
+}
+
+/// Extracted comment text
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Extracted {
+    /// Comment text
+    pub text: String,
+    /// Comment span in source
+    pub span: Range<usize>,
+    /// Text to source offsets mapping
+    ///
+    /// Sorted pairs of text offset and source offset where each continuous piece of text begins.
+    pub map: Vec<(usize, usize)>,
+}
+
+impl Extracted {
+    /// Get source offset of text offset
+    pub fn source_offset(&self, offset: usize) -> usize {
+        let index = self.map.partition_point(|(text, _)| *text <= offset);
+        if index > 0 {
+            let (text, source) = self.map[index - 1];
+            source + offset - text
+        } else {
+            self.span.start
+        }
+    }
+
+    pub(crate) fn shift(mut self, offset: usize) -> Self {
+        self.span = self.span.start + offset..self.span.end + offset;
+        for (_, source) in &mut self.map {
+            *source += offset;
+        }
+        self
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Line<'t> {
+    offset: usize,
+    text: &'t str,
+}
+
+impl<'t> Line<'t> {
+    fn is_blank(&self) -> bool {
+        self.text.trim().is_empty()
+    }
+
+    fn trim_start_matches(self, pat: impl Fn(char) -> bool) -> Self {
+        let text = self.text.trim_start_matches(pat);
+        Self {
+            offset: self.offset + self.text.len() - text.len(),
+            text,
+        }
+    }
+
+    fn skip(self, len: usize) -> Self {
+        let len = len.min(self.text.len());
+        Self {
+            offset: self.offset + len,
+            text: &self.text[len..],
+        }
+    }
+
+    fn indent(&self) -> usize {
+        self.text.len() - self.text.trim_start().len()
+    }
+}
+
+/// Extract text from comment
 pub fn extract(text: &str) -> Option<String> {
-    if let Some(text) = text.strip_prefix("//") {
-        Some(text.trim().into())
-    } else if text.starts_with("/*") && text.ends_with("*/") {
-        let mut lines = text[2..text.len() - 2]
-            .trim_matches('*')
-            .split('\n')
-            .skip_while(|line| line.trim().is_empty())
-            .collect::<Vec<_>>();
-
-        lines.splice(
-            lines.len()
-                - lines
-                    .iter()
-                    .rev()
-                    .take_while(|line| line.trim().is_empty())
-                    .count()..,
-            [],
-        );
+    extract_with(text, &Options::default()).map(|extracted| extracted.text)
+}
 
-        Some(lines.join("\n"))
+/// Extract text from comment using options
+///
+/// Offsets are relative to the comment text.
+pub fn extract_with(text: &str, options: &Options) -> Option<Extracted> {
+    let built = if let Some(body) = text.strip_prefix("//") {
+        let lines = vec![Line {
+            offset: 2,
+            text: body,
+        }];
+        build(lines, options, false)
+    } else if text.starts_with("/*") && text.ends_with("*/") && text.len() >= 4 {
+        let body = text[2..text.len() - 2].trim_end_matches('*');
+        let trimmed = body.trim_start_matches('*');
+        let offset = 2 + body.len() - trimmed.len();
+        let lines = split_lines(trimmed, offset);
+        build(lines, options, true)
     } else {
-        None
+        return None;
+    };
+
+    Some(built.into_extracted(0..text.len()))
+}
+
+/// Iterate over comments in source
+///
+/// Consecutive line comments are merged when [`Options::merge_lines`] is set.
+pub fn comments<'l>(source: &'l str, options: &Options) -> Comments<'l> {
+    Comments {
+        source,
+        lexer: Lexer::from(source).peekable(),
+        options: options.clone(),
+    }
+}
+
+/// Comments iterator
+pub struct Comments<'l> {
+    source: &'l str,
+    lexer: core::iter::Peekable<Lexer<'l>>,
+    options: Options,
+}
+
+impl<'l> Iterator for Comments<'l> {
+    type Item = Extracted;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let first = loop {
+            let lexeme = self.lexer.next()?;
+            if lexeme.token == Token::Comment {
+                break lexeme;
+            }
+        };
+
+        if !self.options.merge_lines || !first.slice.starts_with("//") {
+            return extract_with(first.slice, &self.options)
+                .map(|extracted| extracted.shift(first.span.start));
+        }
+
+        let mut lines = vec![Line {
+            offset: first.span.start + 2,
+            text: &first.slice[2..],
+        }];
+        let mut end = first.span.end;
+
+        while let Some(lexeme) = self.lexer.peek() {
+            if lexeme.token != Token::Comment
+                || !lexeme.slice.starts_with("//")
+                || self.source[end..lexeme.span.start].matches('\n').count() != 1
+            {
+                break;
+            }
+            lines.push(Line {
+                offset: lexeme.span.start + 2,
+                text: &lexeme.slice[2..],
+            });
+            end = lexeme.span.end;
+            self.lexer.next();
+        }
+
+        Some(build(lines, &self.options, false).into_extracted(first.span.start..end))
+    }
+}
+
+fn split_lines(text: &str, offset: usize) -> Vec<Line<'_>> {
+    let mut offset = offset;
+    text.split('\n')
+        .map(|text| {
+            let line = Line { offset, text };
+            offset += text.len() + 1;
+            line
+        })
+        .collect()
+}
+
+fn is_border(chr: char) -> bool {
+    matches!(chr, '*' | '|' | '#' | '│' | '┃' | '║')
+}
+
+fn is_ruler(line: &Line) -> bool {
+    !line.is_blank()
+        && line
+            .text
+            .trim()
+            .chars()
+            .all(|chr| matches!(chr, '*' | '-' | '=' | '#' | '/' | '─' | '━' | '═'))
+}
+
+struct Built<'t> {
+    lines: Vec<Line<'t>>,
+    reflow: bool,
+}
+
+fn build<'t>(mut lines: Vec<Line<'t>>, options: &Options, block: bool) -> Built<'t> {
+    let normalize = options.normalize();
+
+    if block && options.strip_gutter {
+        let gutter = lines
+            .iter()
+            .skip(1)
+            .filter(|line| !line.is_blank())
+            .all(|line| line.text.trim_start().starts_with('*'));
+        if gutter {
+            for line in lines.iter_mut().skip(1) {
+                let stripped = line
+                    .trim_start_matches(char::is_whitespace)
+                    .trim_start_matches(|chr| chr == '*');
+                *line = if stripped.text.starts_with(' ') {
+                    stripped.skip(1)
+                } else {
+                    stripped
+                };
+            }
+        }
+    }
+
+    if options.strip_border {
+        for line in &mut lines {
+            if is_ruler(line) {
+                line.text = "";
+            }
+        }
+        let border = lines
+            .iter()
+            .filter(|line| !line.is_blank())
+            .map(|line| line.text.trim_end().chars().last())
+            .reduce(|a, b| if a == b { a } else { None })
+            .flatten()
+            .filter(|chr| is_border(*chr));
+        if let Some(border) = border {
+            for line in &mut lines {
+                line.text = line
+                    .text
+                    .trim_end()
+                    .strip_suffix(border)
+                    .unwrap_or(line.text);
+            }
+        }
+    }
+
+    if options.dedent {
+        let skip = usize::from(block && !lines[0].is_blank());
+        if skip > 0 {
+            lines[0] = lines[0].trim_start_matches(char::is_whitespace);
+        }
+        let indent = lines
+            .iter()
+            .skip(skip)
+            .filter(|line| !line.is_blank())
+            .map(Line::indent)
+            .min()
+            .unwrap_or(0);
+        for line in lines.iter_mut().skip(skip) {
+            *line = line.skip(indent);
+        }
+    } else if !block {
+        for line in &mut lines {
+            *line = line.trim_start_matches(char::is_whitespace);
+        }
+    }
+
+    if normalize || !block {
+        for line in &mut lines {
+            line.text = line.text.trim_end();
+        }
+    }
+
+    let start = lines
+        .iter()
+        .position(|line| !line.is_blank())
+        .unwrap_or(lines.len());
+    let end = lines
+        .iter()
+        .rposition(|line| !line.is_blank())
+        .map(|end| end + 1)
+        .unwrap_or(start);
+
+    lines.truncate(end);
+    lines.drain(..start);
+
+    Built {
+        lines,
+        reflow: options.reflow,
+    }
+}
+
+impl<'t> Built<'t> {
+    fn into_extracted(self, span: Range<usize>) -> Extracted {
+        let mut text = String::new();
+        let mut map = Vec::new();
+        let mut blank = false;
+
+        for (index, line) in self.lines.into_iter().enumerate() {
+            if self.reflow {
+                if line.is_blank() {
+                    blank = true;
+                    continue;
+                }
+                let line = line.trim_start_matches(char::is_whitespace);
+                if index > 0 {
+                    text.push_str(if blank { "\n\n" } else { " " });
+                }
+                blank = false;
+                map.push((text.len(), line.offset));
+                text.push_str(line.text);
+            } else {
+                if index > 0 {
+                    text.push('\n');
+                }
+                map.push((text.len(), line.offset));
+                text.push_str(line.text);
+            }
+        }
+
+        Extracted { text, span, map }
     }
 }
 
@@ -134,4 +454,147 @@ f gh"#
  *  f gh  *"#
         );
     }
+
+    #[test]
+    fn strip_gutter() {
+        let options = Options {
+            strip_gutter: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            extract_with(
+                r#"/*
+ * abc de
+ *   f gh
+ */"#,
+                &options
+            )
+            .unwrap()
+            .text,
+            "abc de\n  f gh"
+        );
+    }
+
+    #[test]
+    fn strip_border() {
+        let options = Options {
+            strip_gutter: true,
+            strip_border: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            extract_with(
+                r#"/*******
+ * abc de *
+ *  f gh  *
+ *******/"#,
+                &options
+            )
+            .unwrap()
+            .text,
+            "abc de\n f gh"
+        );
+        assert_eq!(
+            extract_with(
+                r#"/*
+ * -------------
+ * abc de
+ * -------------
+ */"#,
+                &options
+            )
+            .unwrap()
+            .text,
+            "abc de"
+        );
+    }
+
+    #[test]
+    fn dedent() {
+        let options = Options {
+            dedent: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            extract_with(
+                r#"/* abc
+     de
+       f gh
+ */"#,
+                &options
+            )
+            .unwrap()
+            .text,
+            "abc\nde\n  f gh"
+        );
+    }
+
+    #[test]
+    fn reflow() {
+        let options = Options {
+            strip_gutter: true,
+            reflow: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            extract_with(
+                r#"/*
+ * abc
+ * de
+ *
+ * f gh
+ */"#,
+                &options
+            )
+            .unwrap()
+            .text,
+            "abc de\n\nf gh"
+        );
+    }
+
+    #[test]
+    fn mapping() {
+        let text = r#"/*
+ * abc de
+ *   f gh
+ */"#;
+        let extracted = extract_with(text, &Options::clean()).unwrap();
+        assert_eq!(extracted.text, "abc de\n  f gh");
+        assert_eq!(extracted.span, 0..text.len());
+        let offset = extracted.source_offset(4);
+        assert_eq!(&text[offset..offset + 2], "de");
+        let offset = extracted.source_offset(9);
+        assert_eq!(&text[offset..offset + 4], "f gh");
+    }
+
+    #[test]
+    fn merge_lines() {
+        let source = r#"
+// abc de
+//   f gh
+int a; // ij
+
+// kl
+"#;
+        let extracted = comments(source, &Options::clean()).collect::<Vec<_>>();
+        assert_eq!(
+            extracted
+                .iter()
+                .map(|e| e.text.as_str())
+                .collect::<Vec<_>>(),
+            ["abc de\n  f gh", "ij", "kl"]
+        );
+        assert_eq!(&source[extracted[0].span.clone()], "// abc de\n//   f gh");
+        let offset = extracted[0].source_offset(9);
+        assert_eq!(&source[offset..offset + 4], "f gh");
+
+        let extracted = comments(source, &Options::default()).collect::<Vec<_>>();
+        assert_eq!(
+            extracted
+                .iter()
+                .map(|e| e.text.as_str())
+                .collect::<Vec<_>>(),
+            ["abc de", "f gh", "ij", "kl"]
+        );
+    }
 }
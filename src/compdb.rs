@@ -0,0 +1,374 @@
+//! Compilation database
+//!
+//! Reads `compile_commands.json` and extracts compiler flags which affects lexing
+//! like language dialect, predefined macros, include paths and forced includes.
+
+use crate::Dialect;
+use std::path::{Path, PathBuf};
+
+/// Compile command of single translation unit
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "RawCommand"))]
+pub struct Command {
+    /// Working directory of compiler
+    pub directory: PathBuf,
+    /// Source file (relative to directory)
+    pub file: PathBuf,
+    /// Compiler command line
+    pub arguments: Vec<String>,
+    /// Output file
+    pub output: Option<PathBuf>,
+}
+
+/// Compile command as stored in database where `arguments` or `command` may be used
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RawCommand {
+    directory: PathBuf,
+    file: PathBuf,
+    #[serde(default)]
+    arguments: Option<Vec<String>>,
+    #[serde(default)]
+    command: Option<String>,
+    #[serde(default)]
+    output: Option<PathBuf>,
+}
+
+#[cfg(feature = "serde")]
+impl From<RawCommand> for Command {
+    fn from(raw: RawCommand) -> Self {
+        Self {
+            directory: raw.directory,
+            file: raw.file,
+            arguments: raw
+                .arguments
+                .unwrap_or_else(|| split_command(raw.command.as_deref().unwrap_or_default())),
+            output: raw.output,
+        }
+    }
+}
+
+impl Command {
+    /// Path to source file
+    pub fn path(&self) -> PathBuf {
+        self.directory.join(&self.file)
+    }
+
+    /// Compiler flags with paths resolved against working directory
+    pub fn flags(&self) -> Flags {
+        let mut flags = Flags::parse(&self.arguments);
+
+        for path in &mut flags.include_paths {
+            *path = self.directory.join(&path);
+        }
+
+        let includes = core::mem::take(&mut flags.includes);
+        flags.includes = includes
+            .into_iter()
+            .map(|include| {
+                let path = self.directory.join(&include);
+                if path.is_file() {
+                    path
+                } else {
+                    flags.find_include(&include).unwrap_or(path)
+                }
+            })
+            .collect();
+
+        flags
+    }
+}
+
+/// Parse compilation database
+#[cfg(feature = "compdb")]
+pub fn parse(json: &str) -> serde_json::Result<Vec<Command>> {
+    serde_json::from_str(json)
+}
+
+/// Load compilation database from file or from `compile_commands.json` in directory
+#[cfg(feature = "compdb")]
+pub fn load(path: impl AsRef<Path>) -> std::io::Result<Vec<Command>> {
+    let path = path.as_ref();
+    let path = if path.is_dir() {
+        path.join("compile_commands.json")
+    } else {
+        path.into()
+    };
+    parse(&std::fs::read_to_string(path)?)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+}
+
+/// Macro definition from command line
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Macro {
+    /// Define macro (`-DNAME=VALUE`)
+    Define {
+        /// Macro name
+        name: String,
+        /// Macro value (`1` when omitted)
+        value: String,
+    },
+    /// Undefine macro (`-UNAME`)
+    Undefine {
+        /// Macro name
+        name: String,
+    },
+}
+
+/// Compiler flags which affects lexing
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Flags {
+    /// Language dialect (`-std=`, `-ansi`)
+    pub dialect: Option<Dialect>,
+    /// Dollar sign in identifiers (`-fdollars-in-identifiers`, `-fno-dollars-in-identifiers`)
+    pub dollars: Option<bool>,
+    /// Macro definitions in order of appearance (`-D`, `-U`)
+    pub macros: Vec<Macro>,
+    /// Include search paths (`-I`, `-iquote`, `-isystem`, `-idirafter`)
+    pub include_paths: Vec<PathBuf>,
+    /// Forcibly included files (`-include`)
+    pub includes: Vec<PathBuf>,
+}
+
+impl Flags {
+    /// Parse compiler arguments
+    ///
+    /// Unknown arguments are ignored.
+    pub fn parse<S: AsRef<str>>(args: &[S]) -> Self {
+        let mut flags = Self::default();
+        let mut args = args.iter().map(AsRef::as_ref);
+
+        fn value<'a>(
+            arg: &'a str,
+            flag: &str,
+            args: &mut impl Iterator<Item = &'a str>,
+        ) -> Option<&'a str> {
+            let rest = arg.strip_prefix(flag)?;
+            if rest.is_empty() {
+                args.next()
+            } else {
+                Some(rest)
+            }
+        }
+
+        while let Some(arg) = args.next() {
+            if let Some(define) = value(arg, "-D", &mut args) {
+                let (name, value) = define.split_once('=').unwrap_or((define, "1"));
+                flags.macros.push(Macro::Define {
+                    name: name.into(),
+                    value: value.into(),
+                });
+            } else if let Some(name) = value(arg, "-U", &mut args) {
+                flags.macros.push(Macro::Undefine { name: name.into() });
+            } else if let Some(std) = arg
+                .strip_prefix("-std=")
+                .or_else(|| arg.strip_prefix("--std="))
+            {
+                if let Ok(dialect) = std.parse() {
+                    flags.dialect = Some(dialect);
+                }
+            } else if arg == "-ansi" {
+                flags.dialect = "c89".parse().ok();
+            } else if arg == "-fdollars-in-identifiers" {
+                flags.dollars = Some(true);
+            } else if arg == "-fno-dollars-in-identifiers" {
+                flags.dollars = Some(false);
+            } else if let Some(path) = value(arg, "-include", &mut args) {
+                flags.includes.push(path.into());
+            } else if let Some(path) = ["-I", "-iquote", "-isystem", "-idirafter"]
+                .iter()
+                .find_map(|flag| value(arg, flag, &mut args))
+            {
+                flags.include_paths.push(path.into());
+            }
+        }
+
+        flags
+    }
+
+    /// Language dialect (default when unspecified) with identifier options applied
+    pub fn dialect_or_default(&self) -> Dialect {
+        let dialect = self.dialect.unwrap_or_default();
+        Dialect {
+            dollars: self.dollars.unwrap_or(dialect.dollars),
+            ..dialect
+        }
+    }
+
+    /// Effective macro definitions in order of definition
+    pub fn defines(&self) -> Vec<(&str, &str)> {
+        let mut defines: Vec<(&str, &str)> = Vec::new();
+        for item in &self.macros {
+            match item {
+                Macro::Define { name, value } => {
+                    defines.retain(|(defined, _)| defined != name);
+                    defines.push((name, value));
+                }
+                Macro::Undefine { name } => defines.retain(|(defined, _)| defined != name),
+            }
+        }
+        defines
+    }
+
+    /// Predefines source code like compiler sees it before translation unit
+    pub fn predefines(&self) -> String {
+        let mut source = String::new();
+        for item in &self.macros {
+            match item {
+                Macro::Define { name, value } => {
+                    source.push_str(&format!("#define {} {}\n", name, value))
+                }
+                Macro::Undefine { name } => source.push_str(&format!("#undef {}\n", name)),
+            }
+        }
+        source
+    }
+
+    /// Find header file in include paths
+    pub fn find_include(&self, name: impl AsRef<Path>) -> Option<PathBuf> {
+        self.include_paths
+            .iter()
+            .map(|dir| dir.join(name.as_ref()))
+            .find(|path| path.is_file())
+    }
+}
+
+/// Split shell command line into arguments
+pub fn split_command(command: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut arg: Option<String> = None;
+    let mut chars = command.chars();
+
+    while let Some(chr) = chars.next() {
+        match chr {
+            chr if chr.is_whitespace() => {
+                if let Some(arg) = arg.take() {
+                    args.push(arg);
+                }
+            }
+            '\'' => {
+                let arg = arg.get_or_insert_with(String::new);
+                for chr in chars.by_ref() {
+                    if chr == '\'' {
+                        break;
+                    }
+                    arg.push(chr);
+                }
+            }
+            '"' => {
+                let arg = arg.get_or_insert_with(String::new);
+                while let Some(chr) = chars.next() {
+                    match chr {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some(chr @ ('"' | '\\' | '$' | '`')) => arg.push(chr),
+                            Some(chr) => {
+                                arg.push('\\');
+                                arg.push(chr);
+                            }
+                            None => arg.push('\\'),
+                        },
+                        chr => arg.push(chr),
+                    }
+                }
+            }
+            '\\' => {
+                let arg = arg.get_or_insert_with(String::new);
+                if let Some(chr) = chars.next() {
+                    arg.push(chr);
+                }
+            }
+            chr => arg.get_or_insert_with(String::new).push(chr),
+        }
+    }
+
+    args.extend(arg);
+    args
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn split() {
+        assert_eq!(
+            split_command(r#"cc -DNAME="\"x y\"" '-DA=1 2' -I\ dir -c a.c"#),
+            ["cc", r#"-DNAME="x y""#, "-DA=1 2", "-I dir", "-c", "a.c"]
+        );
+        assert_eq!(split_command("  cc  ''  "), ["cc", ""]);
+    }
+
+    #[test]
+    fn flags() {
+        let flags = Flags::parse(&[
+            "cc",
+            "-std=gnu99",
+            "-DA",
+            "-D",
+            "B=2",
+            "-DC=",
+            "-UA",
+            "-Iinc",
+            "-isystem",
+            "/usr/include",
+            "-include",
+            "config.h",
+            "-o",
+            "a.o",
+            "a.c",
+        ]);
+        assert_eq!(flags.dialect, "gnu99".parse().ok());
+        assert_eq!(flags.defines(), [("B", "2"), ("C", "")]);
+        assert_eq!(
+            flags.predefines(),
+            "#define A 1\n#define B 2\n#define C \n#undef A\n"
+        );
+        assert_eq!(
+            flags.include_paths,
+            [PathBuf::from("inc"), PathBuf::from("/usr/include")]
+        );
+        assert_eq!(flags.includes, [PathBuf::from("config.h")]);
+        assert_eq!(Flags::parse(&["-ansi"]).dialect, "c89".parse().ok());
+        let flags = Flags::parse(&["-fno-dollars-in-identifiers", "-std=c99"]);
+        assert!(!flags.dialect_or_default().dollars);
+        assert_eq!(flags.dialect_or_default().to_string(), "c99");
+    }
+
+    #[test]
+    fn command_flags() {
+        let command = Command {
+            directory: "/build".into(),
+            file: "src/a.c".into(),
+            arguments: vec![
+                "cc".into(),
+                "-I../inc".into(),
+                "-include".into(),
+                "x.h".into(),
+            ],
+            output: None,
+        };
+        assert_eq!(command.path(), PathBuf::from("/build/src/a.c"));
+        let flags = command.flags();
+        assert_eq!(flags.include_paths, [PathBuf::from("/build/../inc")]);
+        assert_eq!(flags.includes, [PathBuf::from("/build/x.h")]);
+    }
+
+    #[cfg(feature = "compdb")]
+    #[test]
+    fn database() {
+        let commands = parse(
+            r#"[
+  { "directory": "/build", "file": "a.c", "command": "cc -DX=\"1\" -c a.c" },
+  { "directory": "/build", "file": "b.c", "arguments": ["cc", "-std=c11", "b.c"], "output": "b.o" }
+]"#,
+        )
+        .unwrap();
+        assert_eq!(commands[0].arguments, ["cc", "-DX=1", "-c", "a.c"]);
+        assert_eq!(commands[1].flags().dialect, "c11".parse().ok());
+        assert_eq!(commands[1].output, Some(PathBuf::from("b.o")));
+    }
+}
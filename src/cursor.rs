@@ -0,0 +1,254 @@
+use crate::{Lexeme, Token};
+
+/// Group delimiter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Delimiter {
+    /// Parentheses `( )`
+    Paren,
+    /// Brackets `[ ]` (or digraphs `<: :>`)
+    Bracket,
+    /// Braces `{ }` (or digraphs `<% %>`)
+    Brace,
+}
+
+impl Delimiter {
+    /// Delimiter opened by lexeme
+    pub fn open(lexeme: &Lexeme) -> Option<Self> {
+        match (lexeme.token, lexeme.slice) {
+            (Token::Symbol, "(") => Some(Self::Paren),
+            (Token::Symbol, "[" | "<:") => Some(Self::Bracket),
+            (Token::Symbol, "{" | "<%") => Some(Self::Brace),
+            _ => None,
+        }
+    }
+
+    /// Delimiter closed by lexeme
+    pub fn close(lexeme: &Lexeme) -> Option<Self> {
+        match (lexeme.token, lexeme.slice) {
+            (Token::Symbol, ")") => Some(Self::Paren),
+            (Token::Symbol, "]" | ":>") => Some(Self::Bracket),
+            (Token::Symbol, "}" | "%>") => Some(Self::Brace),
+            _ => None,
+        }
+    }
+
+    /// Opening and closing symbols
+    pub fn symbols(self) -> (&'static str, &'static str) {
+        match self {
+            Self::Paren => ("(", ")"),
+            Self::Bracket => ("[", "]"),
+            Self::Brace => ("{", "}"),
+        }
+    }
+}
+
+/// Saved cursor position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Checkpoint(usize);
+
+/// Cursor over lexemes for recursive descent parsing
+///
+/// Lexemes are taken from iterator on demand and kept to allow rewinding to checkpoints.
+pub struct Cursor<'l, I> {
+    lexemes: I,
+    buffer: Vec<Lexeme<'l>>,
+    position: usize,
+}
+
+impl<'l, I> Cursor<'l, I>
+where
+    I: Iterator<Item = Lexeme<'l>>,
+{
+    /// Create cursor over lexemes
+    pub fn new(lexemes: impl IntoIterator<IntoIter = I>) -> Self {
+        Self {
+            lexemes: lexemes.into_iter(),
+            buffer: Vec::new(),
+            position: 0,
+        }
+    }
+
+    /// Number of lexemes passed
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Lexeme `n` positions ahead (`0` is current one)
+    pub fn peek(&mut self, n: usize) -> Option<&Lexeme<'l>> {
+        let index = self.position + n;
+        while self.buffer.len() <= index {
+            self.buffer.push(self.lexemes.next()?);
+        }
+        self.buffer.get(index)
+    }
+
+    /// No lexemes left
+    pub fn is_eof(&mut self) -> bool {
+        self.peek(0).is_none()
+    }
+
+    /// Current lexeme is symbol or identifier with text
+    ///
+    /// Digraphs match corresponding punctuators.
+    pub fn is(&mut self, text: &str) -> bool {
+        self.peek(0)
+            .map(|lexeme| {
+                matches!(lexeme.token, Token::Symbol | Token::Identifier)
+                    && normalize(lexeme.slice) == text
+            })
+            .unwrap_or(false)
+    }
+
+    /// Take current lexeme
+    pub fn bump(&mut self) -> Option<Lexeme<'l>> {
+        let lexeme = self.peek(0)?.clone();
+        self.position += 1;
+        Some(lexeme)
+    }
+
+    /// Take current lexeme if it is symbol or identifier with text
+    pub fn eat(&mut self, text: &str) -> Option<Lexeme<'l>> {
+        if self.is(text) {
+            self.bump()
+        } else {
+            None
+        }
+    }
+
+    /// Save current position
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.position)
+    }
+
+    /// Restore saved position
+    pub fn rewind(&mut self, checkpoint: Checkpoint) {
+        self.position = checkpoint.0;
+    }
+
+    /// Skip comments returning number of skipped lexemes
+    pub fn skip_trivia(&mut self) -> usize {
+        let start = self.position;
+        while self
+            .peek(0)
+            .map(|lexeme| lexeme.token == Token::Comment)
+            .unwrap_or(false)
+        {
+            self.position += 1;
+        }
+        self.position - start
+    }
+
+    /// Skip group starting at current lexeme
+    ///
+    /// Returns closing lexeme of group or `None` when current lexeme does not open group (the
+    /// cursor is not moved then) or group is not closed until end (the cursor is at end then).
+    /// Closing delimiter which does not match innermost group closes enclosing group it matches
+    /// and is ignored otherwise.
+    pub fn skip_balanced(&mut self) -> Option<Lexeme<'l>> {
+        let mut stack = vec![Delimiter::open(self.peek(0)?)?];
+        self.position += 1;
+
+        while let Some(lexeme) = self.bump() {
+            if let Some(delimiter) = Delimiter::open(&lexeme) {
+                stack.push(delimiter);
+            } else if let Some(delimiter) = Delimiter::close(&lexeme) {
+                if let Some(depth) = stack.iter().rposition(|open| *open == delimiter) {
+                    stack.truncate(depth);
+                    if stack.is_empty() {
+                        return Some(lexeme);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl<'l, I> Iterator for Cursor<'l, I>
+where
+    I: Iterator<Item = Lexeme<'l>>,
+{
+    type Item = Lexeme<'l>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.bump()
+    }
+}
+
+/// Replace digraph with punctuator
+fn normalize(slice: &str) -> &str {
+    match slice {
+        "<:" => "[",
+        ":>" => "]",
+        "<%" => "{",
+        "%>" => "}",
+        "%:" => "#",
+        _ => slice,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Lexer;
+
+    #[test]
+    fn peek_and_bump() {
+        let mut cursor = Cursor::new(Lexer::from("int /* c */ a;"));
+        assert_eq!(cursor.peek(2).unwrap().slice, "a");
+        assert!(cursor.is("int"));
+        assert_eq!(cursor.bump().unwrap().slice, "int");
+        assert!(cursor.eat("a").is_none());
+        assert_eq!(cursor.skip_trivia(), 1);
+        assert_eq!(cursor.skip_trivia(), 0);
+        assert_eq!(cursor.eat("a").unwrap().slice, "a");
+        assert_eq!(cursor.position(), 3);
+        assert!(cursor.peek(1).is_none());
+        assert_eq!(cursor.next().unwrap().slice, ";");
+        assert!(cursor.is_eof());
+        assert!(cursor.bump().is_none());
+    }
+
+    #[test]
+    fn rewind() {
+        let mut cursor = Cursor::new(Lexer::from("a b c"));
+        cursor.bump();
+        let checkpoint = cursor.checkpoint();
+        assert_eq!(
+            cursor
+                .by_ref()
+                .map(|lexeme| lexeme.slice)
+                .collect::<Vec<_>>(),
+            ["b", "c"]
+        );
+        cursor.rewind(checkpoint);
+        assert_eq!(cursor.bump().unwrap().slice, "b");
+    }
+
+    #[test]
+    fn balanced() {
+        let mut cursor = Cursor::new(Lexer::from("f(a, (b)[c]) <% x <: 1 ] %> g"));
+        assert!(cursor.skip_balanced().is_none());
+        assert_eq!(cursor.position(), 0);
+        cursor.bump();
+        let close = cursor.skip_balanced().unwrap();
+        assert_eq!(close.span, 11..12);
+        assert!(cursor.is("{"));
+        assert_eq!(cursor.skip_balanced().unwrap().slice, "%>");
+        assert!(cursor.eat("g").is_some());
+
+        // unmatched closing delimiter of other kind is ignored
+        let mut cursor = Cursor::new(Lexer::from("( ] ) x"));
+        assert_eq!(cursor.skip_balanced().unwrap().span, 4..5);
+        // closing delimiter closes enclosing group
+        let mut cursor = Cursor::new(Lexer::from("{ ( } x"));
+        assert_eq!(cursor.skip_balanced().unwrap().span, 4..5);
+        assert!(cursor.is("x"));
+
+        let mut cursor = Cursor::new(Lexer::from("( ( )"));
+        assert!(cursor.skip_balanced().is_none());
+        assert!(cursor.is_eof());
+    }
+}
@@ -0,0 +1,827 @@
+//! Declaration extraction
+//!
+//! Top-level declarations are extracted using tokens only, without preprocessing and type
+//! checking: function prototypes and definitions, typedefs, struct, union and enum definitions
+//! with their members, and global variables. Declarations generated by macros and unusual
+//! declarators (e.g. parameters named by typedef names only) may be misrecognized.
+
+use crate::{
+    doc::{self, Comment, Decl, Kind},
+    Cursor, Delimiter, Dialect, Keyword, Lexeme, Lexer, Location, Token,
+};
+use core::ops::Range;
+
+/// Function prototype or definition
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Function<'l> {
+    /// Function name
+    pub name: &'l str,
+    /// Location of name
+    pub location: Location,
+    /// Declaration span in source (including body)
+    pub span: Range<usize>,
+    /// Tokens before name (return type with specifiers)
+    pub returns: Vec<Lexeme<'l>>,
+    /// Parameters (empty for `(void)`)
+    pub params: Vec<Param<'l>>,
+    /// Takes variable arguments (`...`)
+    pub variadic: bool,
+    /// Function definition with body
+    pub definition: bool,
+    /// Documentation comments
+    pub comments: Vec<Comment>,
+}
+
+/// Function parameter
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Param<'l> {
+    /// Parameter name (`None` for unnamed ones)
+    pub name: Option<&'l str>,
+    /// Parameter span in source
+    pub span: Range<usize>,
+    /// Parameter tokens (specifiers and declarator for K&R function definitions)
+    pub tokens: Vec<Lexeme<'l>>,
+}
+
+/// Global variable
+///
+/// Comma separated declarators result in variables with same span and tokens.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Variable<'l> {
+    /// Variable name
+    pub name: &'l str,
+    /// Location of name
+    pub location: Location,
+    /// Declaration span in source
+    pub span: Range<usize>,
+    /// Declaration tokens including initializer and excluding `;`
+    pub tokens: Vec<Lexeme<'l>>,
+    /// Declared with `extern`
+    pub external: bool,
+    /// Documentation comments
+    pub comments: Vec<Comment>,
+}
+
+/// Type definition
+///
+/// Comma separated declarators result in typedefs with same span and tokens.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Typedef<'l> {
+    /// Type name
+    pub name: &'l str,
+    /// Location of name
+    pub location: Location,
+    /// Declaration span in source
+    pub span: Range<usize>,
+    /// Declaration tokens excluding `;`
+    pub tokens: Vec<Lexeme<'l>>,
+    /// Documentation comments
+    pub comments: Vec<Comment>,
+}
+
+/// Struct or union definition
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Record<'l> {
+    /// `struct` or `union`
+    pub keyword: Keyword,
+    /// Tag name (`None` for anonymous ones)
+    pub name: Option<&'l str>,
+    /// Location of tag name or keyword
+    pub location: Location,
+    /// Span from keyword to closing brace
+    pub span: Range<usize>,
+    /// Fields
+    pub fields: Vec<Field<'l>>,
+    /// Documentation comments
+    pub comments: Vec<Comment>,
+}
+
+/// Struct or union field
+///
+/// Comma separated declarators result in fields with same span and tokens.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Field<'l> {
+    /// Field name (`None` for anonymous struct or union)
+    pub name: Option<&'l str>,
+    /// Location of name or declaration
+    pub location: Location,
+    /// Declaration span in source
+    pub span: Range<usize>,
+    /// Declaration tokens excluding `;`
+    pub tokens: Vec<Lexeme<'l>>,
+    /// Struct, union or enum defined by field type
+    pub definition: Option<Declaration<'l>>,
+    /// Documentation comments
+    pub comments: Vec<Comment>,
+}
+
+/// Enum definition
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Enum<'l> {
+    /// Tag name (`None` for anonymous ones)
+    pub name: Option<&'l str>,
+    /// Location of tag name or keyword
+    pub location: Location,
+    /// Span from keyword to closing brace
+    pub span: Range<usize>,
+    /// Enumerators
+    pub enumerators: Vec<Enumerator<'l>>,
+    /// Documentation comments
+    pub comments: Vec<Comment>,
+}
+
+/// Enumeration constant
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Enumerator<'l> {
+    /// Constant name
+    pub name: &'l str,
+    /// Location of name
+    pub location: Location,
+    /// Span in source
+    pub span: Range<usize>,
+    /// Value tokens after `=`
+    pub value: Vec<Lexeme<'l>>,
+    /// Documentation comments
+    pub comments: Vec<Comment>,
+}
+
+/// Extracted declaration
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Declaration<'l> {
+    /// Function prototype or definition
+    Function(Function<'l>),
+    /// Global variable
+    Variable(Variable<'l>),
+    /// Type definition
+    Typedef(Typedef<'l>),
+    /// Struct or union definition
+    Record(Record<'l>),
+    /// Enum definition
+    Enum(Enum<'l>),
+}
+
+impl<'l> Declaration<'l> {
+    /// Declared name (`None` for anonymous struct, union or enum)
+    pub fn name(&self) -> Option<&'l str> {
+        match self {
+            Self::Function(function) => Some(function.name),
+            Self::Variable(variable) => Some(variable.name),
+            Self::Typedef(typedef) => Some(typedef.name),
+            Self::Record(record) => record.name,
+            Self::Enum(enumeration) => enumeration.name,
+        }
+    }
+
+    /// Span in source
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            Self::Function(function) => function.span.clone(),
+            Self::Variable(variable) => variable.span.clone(),
+            Self::Typedef(typedef) => typedef.span.clone(),
+            Self::Record(record) => record.span.clone(),
+            Self::Enum(enumeration) => enumeration.span.clone(),
+        }
+    }
+
+    /// Documentation comments
+    pub fn comments(&self) -> &[Comment] {
+        match self {
+            Self::Function(function) => &function.comments,
+            Self::Variable(variable) => &variable.comments,
+            Self::Typedef(typedef) => &typedef.comments,
+            Self::Record(record) => &record.comments,
+            Self::Enum(enumeration) => &enumeration.comments,
+        }
+    }
+}
+
+/// Extract top-level declarations in source order
+///
+/// Struct, union and enum definitions precede typedefs and variables which define them.
+pub fn extract(source: &str) -> Vec<Declaration<'_>> {
+    extract_with_dialect(source, Dialect::default())
+}
+
+/// Extract top-level declarations lexing source in specific dialect
+pub fn extract_with_dialect(source: &str, dialect: Dialect) -> Vec<Declaration<'_>> {
+    let lexemes = Lexer::with_dialect(source, dialect).collect::<Vec<_>>();
+    let decls = doc::declarations_in(source, &lexemes);
+    let mut declarations = Vec::new();
+
+    for node in nest(&decls) {
+        let decl = node.decl;
+        let defines_only = decl.kind == Kind::Type && !decl.typedef;
+
+        if let Some(definition) = definition(&lexemes, &node) {
+            declarations.push(definition);
+        }
+        if defines_only {
+            continue;
+        }
+
+        match decl.kind {
+            Kind::Function => {
+                declarations.extend(function(&lexemes, decl).map(Declaration::Function))
+            }
+            Kind::Variable => {
+                let tokens = tokens(&lexemes, &decl.span);
+                declarations.extend(names(decl).map(|(name, location)| {
+                    Declaration::Variable(Variable {
+                        name,
+                        location,
+                        span: decl.span.clone(),
+                        tokens: tokens.clone(),
+                        external: decl.external,
+                        comments: comments(decl),
+                    })
+                }));
+            }
+            Kind::Type => {
+                let tokens = tokens(&lexemes, &decl.span);
+                declarations.extend(names(decl).map(|(name, location)| {
+                    Declaration::Typedef(Typedef {
+                        name,
+                        location,
+                        span: decl.span.clone(),
+                        tokens: tokens.clone(),
+                        comments: comments(decl),
+                    })
+                }));
+            }
+            _ => {}
+        }
+    }
+
+    declarations
+}
+
+/// Declaration with nested ones
+struct Node<'d, 'l> {
+    decl: &'d Decl<'l>,
+    children: Vec<Node<'d, 'l>>,
+}
+
+fn contains(outer: &Range<usize>, inner: &Range<usize>) -> bool {
+    outer.start <= inner.start && inner.end <= outer.end
+}
+
+/// Nest declarations by spans (members always precede enclosing declaration)
+fn nest<'d, 'l>(decls: &'d [Decl<'l>]) -> Vec<Node<'d, 'l>> {
+    let mut nodes: Vec<Node> = Vec::new();
+    for decl in decls {
+        let inner = nodes
+            .iter()
+            .rev()
+            .take_while(|node| contains(&decl.span, &node.decl.span))
+            .count();
+        let children = nodes.split_off(nodes.len() - inner);
+        nodes.push(Node { decl, children });
+    }
+    nodes
+}
+
+fn comments(decl: &Decl) -> Vec<Comment> {
+    decl.comments
+        .iter()
+        .map(|doc| doc.comment.clone())
+        .collect()
+}
+
+/// Declared names with locations
+fn names<'d, 'l>(decl: &'d Decl<'l>) -> impl Iterator<Item = (&'l str, Location)> + 'd {
+    decl.name
+        .zip(decl.location)
+        .into_iter()
+        .chain(decl.others.iter().copied())
+}
+
+/// Indexes of code lexemes within span
+fn code(lexemes: &[Lexeme], span: &Range<usize>) -> Vec<usize> {
+    let start = lexemes.partition_point(|lexeme| lexeme.span.start < span.start);
+    let end = lexemes.partition_point(|lexeme| lexeme.span.start < span.end);
+    (start..end)
+        .filter(|index| lexemes[*index].token != Token::Comment)
+        .collect()
+}
+
+/// Code lexemes within span except terminating `;`
+fn tokens<'l>(lexemes: &[Lexeme<'l>], span: &Range<usize>) -> Vec<Lexeme<'l>> {
+    let mut tokens = code(lexemes, span)
+        .into_iter()
+        .map(|index| lexemes[index].clone())
+        .collect::<Vec<_>>();
+    if tokens.last().map(|lexeme| lexeme.slice) == Some(";") {
+        tokens.pop();
+    }
+    tokens
+}
+
+fn function<'l>(lexemes: &[Lexeme<'l>], decl: &Decl<'l>) -> Option<Function<'l>> {
+    let code = code(lexemes, &decl.span);
+    let name = code
+        .iter()
+        .position(|index| Some(lexemes[*index].location) == decl.location)?;
+    let open = name + 1;
+    if lexemes[*code.get(open)?].slice != "(" {
+        return None;
+    }
+
+    let mut depth = 0usize;
+    let close = code[open..]
+        .iter()
+        .position(|&index| {
+            if Delimiter::open(&lexemes[index]).is_some() {
+                depth += 1;
+            } else if Delimiter::close(&lexemes[index]).is_some() {
+                depth -= 1;
+            }
+            depth == 0
+        })
+        .map(|position| open + position)
+        .unwrap_or(code.len());
+
+    // parameter declarations of K&R definition (`int f(a) int a; {`)
+    let mut declared = Vec::new();
+    if decl.body {
+        let body = code[close..]
+            .iter()
+            .position(|&index| Delimiter::open(&lexemes[index]) == Some(Delimiter::Brace))
+            .map(|position| close + position)
+            .unwrap_or(code.len());
+        for declaration in split(lexemes, code.get(close + 1..body).unwrap_or_default(), ";") {
+            declared.extend(knr_params(lexemes, &declaration));
+        }
+    }
+
+    let mut variadic = false;
+    let params = split(lexemes, &code[open + 1..close], ",")
+        .into_iter()
+        .filter(|param| match param.as_slice() {
+            [index] if lexemes[*index].slice == "..." => {
+                variadic = true;
+                false
+            }
+            [index] => lexemes[*index].keyword() != Some(Keyword::Void),
+            _ => true,
+        })
+        .map(|param| {
+            let param = self::param(lexemes, &param);
+            declared
+                .iter()
+                .find(|declared| param.name.is_some() && declared.name == param.name)
+                .cloned()
+                .unwrap_or(param)
+        })
+        .collect();
+
+    Some(Function {
+        name: lexemes[code[name]].slice,
+        location: lexemes[code[name]].location,
+        span: decl.span.clone(),
+        returns: code[..name]
+            .iter()
+            .map(|index| lexemes[*index].clone())
+            .collect(),
+        params,
+        variadic,
+        definition: decl.body,
+        comments: comments(decl),
+    })
+}
+
+/// Split code lexemes by top-level separator (empty last part is omitted)
+fn split(lexemes: &[Lexeme], code: &[usize], separator: &str) -> Vec<Vec<usize>> {
+    let mut parts = Vec::new();
+    let mut part = Vec::new();
+    let mut depth = 0usize;
+    for &index in code {
+        let lexeme = &lexemes[index];
+        if Delimiter::open(lexeme).is_some() {
+            depth += 1;
+        } else if Delimiter::close(lexeme).is_some() {
+            depth = depth.saturating_sub(1);
+        } else if depth == 0 && lexeme.slice == separator {
+            parts.push(core::mem::take(&mut part));
+            continue;
+        }
+        part.push(index);
+    }
+    if !part.is_empty() {
+        parts.push(part);
+    }
+    parts
+}
+
+fn param<'l>(lexemes: &[Lexeme<'l>], tokens: &[usize]) -> Param<'l> {
+    Param {
+        name: doc::declared_name(lexemes, tokens).map(|index| lexemes[index].slice),
+        span: lexemes[tokens[0]].span.start..lexemes[*tokens.last().unwrap()].span.end,
+        tokens: tokens.iter().map(|index| lexemes[*index].clone()).collect(),
+    }
+}
+
+/// Parameters declared by K&R parameter declaration
+///
+/// Further declarators get specifiers of the first one (`int a, *b` declares `int *b`).
+fn knr_params<'l>(lexemes: &[Lexeme<'l>], declaration: &[usize]) -> Vec<Param<'l>> {
+    let declarators = split(lexemes, declaration, ",");
+    let Some(first) = declarators.first() else {
+        return Vec::new();
+    };
+    let name = doc::declared_name(lexemes, first);
+    let specifiers = first
+        .iter()
+        .take_while(|&&index| Some(index) != name && !matches!(lexemes[index].slice, "*" | "("))
+        .copied()
+        .collect::<Vec<_>>();
+
+    declarators
+        .iter()
+        .enumerate()
+        .filter(|(_, declarator)| !declarator.is_empty())
+        .map(|(position, declarator)| {
+            let mut param = if position == 0 {
+                param(lexemes, declarator)
+            } else {
+                let tokens = specifiers
+                    .iter()
+                    .chain(declarator)
+                    .copied()
+                    .collect::<Vec<_>>();
+                param(lexemes, &tokens)
+            };
+            param.span =
+                lexemes[declarator[0]].span.start..lexemes[*declarator.last().unwrap()].span.end;
+            param
+        })
+        .collect()
+}
+
+/// Struct, union or enum defined at top level of declaration
+fn definition<'l>(lexemes: &[Lexeme<'l>], node: &Node<'_, 'l>) -> Option<Declaration<'l>> {
+    let code = code(lexemes, &node.decl.span);
+    let mut depth = 0usize;
+
+    for (position, &index) in code.iter().enumerate() {
+        let lexeme = &lexemes[index];
+        if Delimiter::open(lexeme).is_some() {
+            depth += 1;
+            continue;
+        }
+        if Delimiter::close(lexeme).is_some() {
+            depth = depth.saturating_sub(1);
+            continue;
+        }
+        let keyword = match lexeme.keyword() {
+            Some(keyword @ (Keyword::Struct | Keyword::Union | Keyword::Enum)) if depth == 0 => {
+                keyword
+            }
+            _ => continue,
+        };
+
+        let mut rest = code[position + 1..].iter().map(|index| &lexemes[*index]);
+        let mut next = rest.next()?;
+        let mut name = None;
+        if next.token == Token::Identifier && next.keyword().is_none() {
+            name = Some(next);
+            next = rest.next()?;
+        }
+        if Delimiter::open(next) != Some(Delimiter::Brace) {
+            continue;
+        }
+
+        let mut cursor = Cursor::new(core::iter::once(next.clone()).chain(rest.cloned()));
+        let end = match cursor.skip_balanced() {
+            Some(close) => close.span.end,
+            None => node.decl.span.end,
+        };
+        let body = next.span.start..end;
+        let span = lexeme.span.start..end;
+        let location = name.unwrap_or(lexeme).location;
+        let members = node
+            .children
+            .iter()
+            .filter(|child| contains(&body, &child.decl.span));
+
+        return Some(if keyword == Keyword::Enum {
+            Declaration::Enum(Enum {
+                name: name.map(|name| name.slice),
+                location,
+                span,
+                enumerators: members
+                    .filter(|member| member.decl.kind == Kind::Constant)
+                    .filter_map(|member| enumerator(lexemes, member.decl))
+                    .collect(),
+                comments: comments(node.decl),
+            })
+        } else {
+            Declaration::Record(Record {
+                keyword,
+                name: name.map(|name| name.slice),
+                location,
+                span,
+                fields: members.flat_map(|member| fields(lexemes, member)).collect(),
+                comments: comments(node.decl),
+            })
+        });
+    }
+
+    None
+}
+
+fn fields<'l>(lexemes: &[Lexeme<'l>], node: &Node<'_, 'l>) -> Vec<Field<'l>> {
+    let decl = node.decl;
+    let tokens = tokens(lexemes, &decl.span);
+    let definition = definition(lexemes, node);
+
+    match decl.kind {
+        Kind::Field => names(decl)
+            .map(|(name, location)| Field {
+                name: Some(name),
+                location,
+                span: decl.span.clone(),
+                tokens: tokens.clone(),
+                definition: definition.clone(),
+                comments: comments(decl),
+            })
+            .collect(),
+        // anonymous struct or union
+        Kind::Type if definition.is_some() => vec![Field {
+            name: None,
+            location: tokens[0].location,
+            span: decl.span.clone(),
+            tokens,
+            definition,
+            comments: comments(decl),
+        }],
+        _ => Vec::new(),
+    }
+}
+
+fn enumerator<'l>(lexemes: &[Lexeme<'l>], decl: &Decl<'l>) -> Option<Enumerator<'l>> {
+    let tokens = tokens(lexemes, &decl.span);
+    let value = tokens
+        .iter()
+        .position(|lexeme| lexeme.slice == "=")
+        .map(|position| tokens[position + 1..].to_vec())
+        .unwrap_or_default();
+
+    Some(Enumerator {
+        name: decl.name?,
+        location: decl.location?,
+        span: decl.span.clone(),
+        value,
+        comments: comments(decl),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn text(tokens: &[Lexeme]) -> String {
+        tokens
+            .iter()
+            .map(|lexeme| lexeme.slice)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn functions() {
+        let source = r#"
+/** Open file */
+extern int open(const char *path, int flags, ...);
+static inline struct point *make(void) { return 0; }
+void (*signal(int sig, void (*handler)(int)))(int);
+size_t strlen(const char *);
+"#;
+        let declarations = extract(source);
+        let functions = declarations
+            .iter()
+            .map(|declaration| match declaration {
+                Declaration::Function(function) => function,
+                _ => panic!("unexpected {:?}", declaration),
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(functions.len(), 4);
+        let open = functions[0];
+        assert_eq!(open.name, "open");
+        assert_eq!(open.location.to_string(), "3:12");
+        assert_eq!(text(&open.returns), "extern int");
+        assert_eq!(
+            open.params
+                .iter()
+                .map(|param| (param.name, text(&param.tokens)))
+                .collect::<Vec<_>>(),
+            [
+                (Some("path"), "const char * path".into()),
+                (Some("flags"), "int flags".into())
+            ]
+        );
+        assert_eq!(&source[open.params[0].span.clone()], "const char *path");
+        assert!(open.variadic && !open.definition);
+        assert_eq!(open.comments[0].text, "Open file");
+        assert_eq!(&source[open.span.clone()], &source[18..68]);
+
+        let make = functions[1];
+        assert_eq!(text(&make.returns), "static inline struct point *");
+        assert!(make.params.is_empty() && make.definition);
+        assert!(make.comments.is_empty());
+
+        let signal = functions[2];
+        assert_eq!(signal.name, "signal");
+        assert_eq!(
+            signal
+                .params
+                .iter()
+                .map(|param| param.name)
+                .collect::<Vec<_>>(),
+            [Some("sig"), Some("handler")]
+        );
+
+        assert_eq!(functions[3].params[0].name, None);
+    }
+
+    #[test]
+    fn types() {
+        let source = r#"
+/// Point
+typedef struct point {
+    int x, y; ///< Coordinates
+    union {
+        float f;
+        struct { int a; } s;
+    };
+} point_t, *point_p;
+
+enum color { RED, GREEN = 2 /**< Green */, BLUE = GREEN << 1 };
+struct forward;
+"#;
+        let declarations = extract(source);
+        assert_eq!(
+            declarations
+                .iter()
+                .map(|declaration| declaration.name())
+                .collect::<Vec<_>>(),
+            [
+                Some("point"),
+                Some("point_t"),
+                Some("point_p"),
+                Some("color")
+            ]
+        );
+
+        let Declaration::Record(record) = &declarations[0] else {
+            unreachable!()
+        };
+        assert_eq!(record.keyword, Keyword::Struct);
+        assert_eq!(record.location.to_string(), "3:16");
+        assert!(source[record.span.clone()].starts_with("struct point {"));
+        assert!(source[record.span.clone()].ends_with('}'));
+        assert_eq!(record.comments[0].text, "Point");
+        assert_eq!(
+            record
+                .fields
+                .iter()
+                .map(|field| (field.name, text(&field.tokens)))
+                .collect::<Vec<_>>(),
+            [
+                (Some("x"), "int x , y".into()),
+                (Some("y"), "int x , y".into()),
+                (None, "union { float f ; struct { int a ; } s ; }".into()),
+            ]
+        );
+        assert_eq!(record.fields[1].comments[0].text, "Coordinates");
+        let Some(Declaration::Record(union)) = &record.fields[2].definition else {
+            unreachable!()
+        };
+        assert_eq!(union.keyword, Keyword::Union);
+        assert_eq!(union.fields[0].name, Some("f"));
+        let Some(Declaration::Record(inner)) = &union.fields[1].definition else {
+            unreachable!()
+        };
+        assert_eq!(inner.fields[0].name, Some("a"));
+
+        let Declaration::Typedef(typedef) = &declarations[2] else {
+            unreachable!()
+        };
+        assert_eq!(
+            text(&typedef.tokens),
+            "typedef struct point { int x , y ; union { float f ; struct { int a ; } s ; } ; } point_t , * point_p"
+        );
+        assert_eq!(typedef.comments[0].text, "Point");
+
+        let Declaration::Enum(color) = &declarations[3] else {
+            unreachable!()
+        };
+        assert_eq!(
+            color
+                .enumerators
+                .iter()
+                .map(|enumerator| (enumerator.name, text(&enumerator.value)))
+                .collect::<Vec<_>>(),
+            [
+                ("RED", "".into()),
+                ("GREEN", "2".into()),
+                ("BLUE", "GREEN << 1".into())
+            ]
+        );
+        assert_eq!(color.enumerators[1].comments[0].text, "Green");
+    }
+
+    #[test]
+    fn variables() {
+        let source = "extern const int a, b[2];\nstruct { int x; } v = { 1 };\nint c = f(1, 2);\n";
+        let declarations = extract(source);
+        let variables = declarations
+            .iter()
+            .filter_map(|declaration| match declaration {
+                Declaration::Variable(variable) => {
+                    Some((variable.name, variable.external, text(&variable.tokens)))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            variables,
+            [
+                ("a", true, "extern const int a , b [ 2 ]".into()),
+                ("b", true, "extern const int a , b [ 2 ]".into()),
+                ("v", false, "struct { int x ; } v = { 1 }".into()),
+                ("c", false, "int c = f ( 1 , 2 )".into()),
+            ]
+        );
+        assert!(matches!(&declarations[2], Declaration::Record(record) if record.name.is_none()));
+    }
+
+    #[test]
+    fn knr() {
+        let source = "int add(a, b, c) long a, *b; { return a + *b + c; }\nint z;\n";
+        let declarations = extract(source);
+        assert_eq!(declarations.len(), 2);
+        let Declaration::Function(add) = &declarations[0] else {
+            unreachable!()
+        };
+        assert!(add.definition);
+        assert_eq!(
+            add.params
+                .iter()
+                .map(|param| (param.name, text(&param.tokens)))
+                .collect::<Vec<_>>(),
+            [
+                (Some("a"), "long a".into()),
+                (Some("b"), "long * b".into()),
+                (Some("c"), "c".into())
+            ]
+        );
+        assert_eq!(&source[add.params[1].span.clone()], "*b");
+        assert!(source[add.span.clone()].ends_with("*b + c; }"));
+        assert_eq!(declarations[1].name(), Some("z"));
+
+        // prototypes are not affected
+        let declarations = extract("int f(a, b);\nint g(void) __attribute__((pure));\nint x;");
+        assert_eq!(
+            declarations
+                .iter()
+                .map(|declaration| declaration.name())
+                .collect::<Vec<_>>(),
+            [Some("f"), Some("g"), Some("x")]
+        );
+
+        // macro call is not taken for K&R declarator
+        let source = "LIST_HEAD(pending)\nstatic int count;\nint get(void);\nstatic struct s { int a; } g;\n";
+        let declarations = extract(source);
+        let names = declarations
+            .iter()
+            .map(|declaration| declaration.name())
+            .collect::<Vec<_>>();
+        assert!(names.contains(&Some("get")));
+        let record = declarations
+            .iter()
+            .find_map(|declaration| match declaration {
+                Declaration::Record(record) if record.name == Some("s") => Some(record),
+                _ => None,
+            });
+        assert_eq!(record.unwrap().fields[0].name, Some("a"));
+    }
+
+    #[test]
+    fn dialect() {
+        let source = "/// Counter\nint count;\n";
+        assert_eq!(extract(source)[0].comments()[0].text, "Counter");
+        let c89 = extract_with_dialect(source, "c89".parse().unwrap());
+        assert_eq!(c89[0].name(), Some("count"));
+        assert!(c89[0].comments().is_empty());
+    }
+}
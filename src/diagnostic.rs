@@ -0,0 +1,75 @@
+use core::ops::Range;
+
+/// Diagnostic severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Severity {
+    /// Suspicious but valid code
+    Warning,
+    /// Invalid code
+    Error,
+}
+
+impl core::fmt::Display for Severity {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(match self {
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
+
+/// Diagnostic kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DiagnosticKind {
+    /// `/*` within block comment
+    NestedComment,
+    /// Line comment continued by line splice
+    SplicedLineComment,
+    /// Block comment without `*/`
+    UnterminatedComment,
+}
+
+impl DiagnosticKind {
+    /// Diagnostic severity
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::NestedComment | Self::SplicedLineComment => Severity::Warning,
+            Self::UnterminatedComment => Severity::Error,
+        }
+    }
+
+    /// Diagnostic message
+    pub fn message(&self) -> &'static str {
+        match self {
+            Self::NestedComment => "\"/*\" within block comment",
+            Self::SplicedLineComment => "multi-line comment",
+            Self::UnterminatedComment => "unterminated comment",
+        }
+    }
+}
+
+/// Lexer diagnostic
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diagnostic {
+    /// Diagnostic kind
+    pub kind: DiagnosticKind,
+    /// Position in source code
+    pub span: Range<usize>,
+}
+
+impl core::ops::Deref for Diagnostic {
+    type Target = DiagnosticKind;
+
+    fn deref(&self) -> &Self::Target {
+        &self.kind
+    }
+}
+
+impl core::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}: {}", self.severity(), self.message())
+    }
+}
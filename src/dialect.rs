@@ -0,0 +1,149 @@
+use crate::{ident::Charset, Keyword};
+
+/// C language standard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Standard {
+    /// ANSI C (C89/C90)
+    C89,
+    /// ISO C99
+    C99,
+    /// ISO C11
+    C11,
+    /// ISO C17
+    C17,
+    /// ISO C23
+    C23,
+}
+
+/// C language dialect
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Dialect {
+    /// Language standard
+    pub standard: Standard,
+    /// GNU extensions enabled
+    pub gnu: bool,
+    /// Dollar sign allowed in identifiers (`-fdollars-in-identifiers`)
+    pub dollars: bool,
+}
+
+impl Default for Dialect {
+    /// GNU C17 like GCC does by default
+    fn default() -> Self {
+        Self {
+            standard: Standard::C17,
+            gnu: true,
+            dollars: true,
+        }
+    }
+}
+
+impl core::str::FromStr for Dialect {
+    type Err = ();
+
+    /// Parse dialect as in `-std=` compiler option
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (gnu, version) = if let Some(version) = s.strip_prefix("gnu") {
+            (true, version)
+        } else if let Some(version) = s.strip_prefix("iso9899:") {
+            (false, version)
+        } else if let Some(version) = s.strip_prefix('c') {
+            (false, version)
+        } else {
+            return Err(());
+        };
+
+        let standard = match version {
+            "89" | "90" | "1990" | "199409" => Standard::C89,
+            "99" | "9x" | "1999" | "199x" => Standard::C99,
+            "11" | "1x" | "2011" => Standard::C11,
+            "17" | "18" | "2017" | "2018" => Standard::C17,
+            "23" | "2x" | "2024" => Standard::C23,
+            _ => return Err(()),
+        };
+
+        Ok(Self {
+            standard,
+            gnu,
+            dollars: true,
+        })
+    }
+}
+
+impl core::fmt::Display for Dialect {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let version = match self.standard {
+            Standard::C89 => "89",
+            Standard::C99 => "99",
+            Standard::C11 => "11",
+            Standard::C17 => "17",
+            Standard::C23 => "23",
+        };
+        write!(f, "{}{}", if self.gnu { "gnu" } else { "c" }, version)
+    }
+}
+
+impl Dialect {
+    /// Line comments (`//`) is supported
+    pub fn line_comments(&self) -> bool {
+        self.gnu || self.standard >= Standard::C99
+    }
+
+    /// Extended characters allowed in identifiers
+    ///
+    /// C89 allows ASCII only, C99 to C17 allow ranges of C11 Annex D and C23 allows Unicode
+    /// `XID_Start` and `XID_Continue` characters.
+    pub(crate) fn identifier_charset(&self) -> Charset {
+        match self.standard {
+            Standard::C89 => Charset::Ascii,
+            Standard::C23 => Charset::Xid,
+            _ => Charset::Annex,
+        }
+    }
+
+    /// Keyword is reserved in this dialect
+    pub fn is_keyword(&self, keyword: Keyword) -> bool {
+        keyword.standard() <= self.standard || (self.gnu && keyword == Keyword::Inline)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            "gnu99".parse(),
+            Ok(Dialect {
+                standard: Standard::C99,
+                gnu: true,
+                dollars: true,
+            })
+        );
+        assert_eq!(
+            "iso9899:1990".parse(),
+            Ok(Dialect {
+                standard: Standard::C89,
+                gnu: false,
+                dollars: true,
+            })
+        );
+        assert_eq!("c2x".parse::<Dialect>().unwrap().to_string(), "c23");
+        assert_eq!("c++17".parse::<Dialect>(), Err(()));
+    }
+
+    #[test]
+    fn keywords() {
+        let c89 = "c89".parse::<Dialect>().unwrap();
+        assert!(c89.is_keyword(Keyword::Int));
+        assert!(!c89.is_keyword(Keyword::Restrict));
+        assert!(!c89.is_keyword(Keyword::Inline));
+        assert!("gnu89"
+            .parse::<Dialect>()
+            .unwrap()
+            .is_keyword(Keyword::Inline));
+        assert!(Dialect::default().is_keyword(Keyword::StaticAssert));
+    }
+}
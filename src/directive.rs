@@ -0,0 +1,42 @@
+/// Position is preceded only by whitespace in its line (e.g. directive introducer `#`)
+pub fn at_line_start(source: &str, pos: usize) -> bool {
+    source[..pos]
+        .rsplit('\n')
+        .next()
+        .map(|line| line.trim().is_empty())
+        .unwrap_or(true)
+}
+
+/// Find end of directive line including line splices
+pub fn end(source: &str, start: usize) -> usize {
+    let mut end = start;
+    loop {
+        match source[end..].find('\n') {
+            Some(pos) => {
+                end += pos;
+                if source[..end].trim_end_matches('\r').ends_with('\\') {
+                    end += 1;
+                } else {
+                    break end;
+                }
+            }
+            None => break source.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lines() {
+        let src = "a\n  # define X \\\r\n 1\n#x";
+        assert!(at_line_start(src, 0));
+        assert!(!at_line_start(src, 1));
+        assert!(at_line_start(src, 4));
+        assert!(at_line_start(src, 21));
+        assert_eq!(end(src, 4), 20);
+        assert_eq!(end(src, 21), src.len());
+    }
+}
@@ -0,0 +1,625 @@
+//! Documentation comments
+//!
+//! Recognizes Doxygen and Javadoc comment styles and parses commands like `@brief` or
+//! `@param[in]` into structured blocks.
+
+mod attach;
+
+pub use attach::{attach, Attached, Kind};
+pub(crate) use attach::{declarations, declarations_in, declared_name, Decl};
+
+/// Documentation comment style
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Style {
+    /// Javadoc block `/** ... */`
+    JavaDoc,
+    /// Qt block `/*! ... */`
+    Qt,
+    /// Line `/// ...`
+    Line,
+    /// Qt line `//! ...`
+    QtLine,
+}
+
+impl Style {
+    /// Block comment style
+    pub fn is_block(&self) -> bool {
+        matches!(self, Self::JavaDoc | Self::Qt)
+    }
+
+    /// Line comment style
+    pub fn is_line(&self) -> bool {
+        matches!(self, Self::Line | Self::QtLine)
+    }
+}
+
+/// Documentation comment
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Comment {
+    /// Comment style
+    pub style: Style,
+    /// Documents previous entity (`/**<`, `///<`)
+    pub trailing: bool,
+    /// Text without delimiters and star gutter
+    pub text: String,
+}
+
+impl Comment {
+    /// Parse raw comment lexeme
+    ///
+    /// Returns `None` for regular (non-documentation) comments.
+    pub fn parse(slice: &str) -> Option<Self> {
+        let (style, body) = split(slice)?;
+
+        let (trailing, body) = if let Some(body) = body.strip_prefix('<') {
+            (true, body)
+        } else {
+            (false, body)
+        };
+
+        let text = if style.is_block() {
+            strip_gutter(body)
+        } else {
+            body.strip_prefix(' ').unwrap_or(body).trim_end().into()
+        };
+
+        Some(Self {
+            style,
+            trailing,
+            text,
+        })
+    }
+
+    /// Parse commands
+    pub fn doc(&self) -> Doc {
+        Doc::parse(&self.text)
+    }
+}
+
+fn split(slice: &str) -> Option<(Style, &str)> {
+    if let Some(body) = slice.strip_prefix("///") {
+        if body.starts_with('/') {
+            None
+        } else {
+            Some((Style::Line, body))
+        }
+    } else if let Some(body) = slice.strip_prefix("//!") {
+        Some((Style::QtLine, body))
+    } else if slice.len() < 5 || !slice.ends_with("*/") {
+        None
+    } else if let Some(body) = slice.strip_prefix("/**") {
+        if body.starts_with('*') || body == "/" {
+            // banners like `/*****` and empty `/**/`
+            None
+        } else {
+            Some((Style::JavaDoc, &body[..body.len() - 2]))
+        }
+    } else {
+        slice
+            .strip_prefix("/*!")
+            .map(|body| (Style::Qt, &body[..body.len() - 2]))
+    }
+}
+
+fn strip_gutter(body: &str) -> String {
+    let mut lines = body.split('\n').map(|line| line.trim_end_matches('\r'));
+    let first = lines.next().unwrap_or_default().trim();
+    let rest = lines.collect::<Vec<_>>();
+
+    let gutter = rest
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .all(|line| line.trim_start().starts_with('*'));
+
+    let indent = if gutter {
+        0
+    } else {
+        rest.iter()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0)
+    };
+
+    let rest = rest.into_iter().map(|line| {
+        if gutter {
+            let line = line.trim_start().trim_start_matches('*');
+            line.strip_prefix(' ').unwrap_or(line)
+        } else {
+            line.get(indent..).unwrap_or_default()
+        }
+    });
+
+    let lines = core::iter::once(first)
+        .chain(rest)
+        .map(str::trim_end)
+        .collect::<Vec<_>>();
+
+    let start = lines
+        .iter()
+        .position(|line| !line.is_empty())
+        .unwrap_or(lines.len());
+    let end = lines
+        .iter()
+        .rposition(|line| !line.is_empty())
+        .map(|end| end + 1)
+        .unwrap_or(start);
+
+    lines[start..end].join("\n")
+}
+
+/// Parameter direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Direction {
+    /// `[in]`
+    In,
+    /// `[out]`
+    Out,
+    /// `[in,out]`
+    InOut,
+}
+
+/// Documentation block
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Block {
+    /// Plain text paragraph
+    Paragraph(String),
+    /// `@brief` description
+    Brief(String),
+    /// `@param` description
+    Param {
+        /// Parameter name
+        name: String,
+        /// Parameter direction
+        dir: Option<Direction>,
+        /// Description
+        text: String,
+    },
+    /// `@return` description
+    Return(String),
+    /// `@retval` description
+    RetVal {
+        /// Returned value
+        value: String,
+        /// Description
+        text: String,
+    },
+    /// `@code ... @endcode` block
+    Code {
+        /// Language (`@code{.c}`)
+        lang: Option<String>,
+        /// Code text
+        text: String,
+    },
+    /// `@verbatim ... @endverbatim` block
+    Verbatim(String),
+    /// Any other block command like `@note` or `@see`
+    Tag {
+        /// Command name
+        name: String,
+        /// Command text
+        text: String,
+    },
+}
+
+impl Block {
+    fn text_mut(&mut self) -> &mut String {
+        match self {
+            Self::Paragraph(text)
+            | Self::Brief(text)
+            | Self::Param { text, .. }
+            | Self::Return(text)
+            | Self::RetVal { text, .. }
+            | Self::Code { text, .. }
+            | Self::Verbatim(text)
+            | Self::Tag { text, .. } => text,
+        }
+    }
+}
+
+/// Parsed documentation
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Doc {
+    /// Documentation blocks
+    pub blocks: Vec<Block>,
+}
+
+/// Commands which are part of text
+const INLINE_COMMANDS: &[&str] = &[
+    "a", "b", "c", "e", "em", "p", "ref", "link", "endlink", "n", "anchor", "f",
+];
+
+impl Doc {
+    /// Parse documentation text
+    ///
+    /// The text should be already stripped from comment delimiters and gutter (see
+    /// [`Comment::text`]).
+    pub fn parse(text: &str) -> Self {
+        let mut blocks = Vec::new();
+        let mut block: Option<Block> = None;
+        // Closing command for code and verbatim blocks
+        let mut verbatim: Option<&str> = None;
+
+        for line in text.lines() {
+            if let Some(end) = verbatim {
+                if let Some(pos) = find_command(line, end) {
+                    let text = block.as_mut().unwrap().text_mut();
+                    if !line[..pos].trim().is_empty() {
+                        push_line(text, &line[..pos], true);
+                    }
+                    text.truncate(text.trim_end().len());
+                    blocks.extend(block.take());
+                    verbatim = None;
+                } else {
+                    push_line(block.as_mut().unwrap().text_mut(), line, true);
+                }
+                continue;
+            }
+
+            let trimmed = line.trim();
+
+            if trimmed.is_empty() {
+                blocks.extend(block.take());
+                continue;
+            }
+
+            if let Some((name, rest)) = split_command(trimmed) {
+                if !INLINE_COMMANDS.contains(&name) {
+                    blocks.extend(block.take());
+
+                    match name {
+                        "code" => {
+                            let (lang, rest) = split_lang(rest);
+                            let mut text = String::new();
+                            push_line(&mut text, rest, true);
+                            block = Some(Block::Code { lang, text });
+                            verbatim = Some("endcode");
+                        }
+                        "verbatim" => {
+                            let mut text = String::new();
+                            push_line(&mut text, rest, true);
+                            block = Some(Block::Verbatim(text));
+                            verbatim = Some("endverbatim");
+                        }
+                        "brief" | "short" => block = Some(Block::Brief(rest.trim().into())),
+                        "param" => {
+                            let (dir, rest) = split_dir(rest);
+                            let (name, text) = split_word(rest);
+                            block = Some(Block::Param {
+                                name: name.into(),
+                                dir,
+                                text: text.into(),
+                            });
+                        }
+                        "return" | "returns" | "result" => {
+                            block = Some(Block::Return(rest.trim().into()))
+                        }
+                        "retval" => {
+                            let (value, text) = split_word(rest);
+                            block = Some(Block::RetVal {
+                                value: value.into(),
+                                text: text.into(),
+                            });
+                        }
+                        _ => {
+                            block = Some(Block::Tag {
+                                name: name.into(),
+                                text: rest.trim().into(),
+                            })
+                        }
+                    }
+                    continue;
+                }
+            }
+
+            if let Some(block) = &mut block {
+                push_line(block.text_mut(), trimmed, false);
+            } else {
+                block = Some(Block::Paragraph(trimmed.into()));
+            }
+        }
+
+        blocks.extend(block);
+
+        Self { blocks }
+    }
+
+    /// Brief description
+    ///
+    /// Uses `@brief` when present or first paragraph otherwise.
+    pub fn brief(&self) -> Option<&str> {
+        self.blocks
+            .iter()
+            .find_map(|block| match block {
+                Block::Brief(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .or_else(|| {
+                self.blocks.iter().find_map(|block| match block {
+                    Block::Paragraph(text) => Some(text.as_str()),
+                    _ => None,
+                })
+            })
+    }
+
+    /// Parameters descriptions
+    pub fn params(&self) -> impl Iterator<Item = (&str, Option<Direction>, &str)> {
+        self.blocks.iter().filter_map(|block| match block {
+            Block::Param { name, dir, text } => Some((name.as_str(), *dir, text.as_str())),
+            _ => None,
+        })
+    }
+
+    /// Return value description
+    pub fn returns(&self) -> Option<&str> {
+        self.blocks.iter().find_map(|block| match block {
+            Block::Return(text) => Some(text.as_str()),
+            _ => None,
+        })
+    }
+}
+
+fn push_line(text: &mut String, line: &str, verbatim: bool) {
+    let line = if verbatim { line.trim_end() } else { line };
+    if verbatim && text.is_empty() && line.trim().is_empty() {
+        return;
+    }
+    if !text.is_empty() {
+        text.push('\n');
+    }
+    text.push_str(line);
+}
+
+fn is_command_start(chr: char) -> bool {
+    chr == '@' || chr == '\\'
+}
+
+fn split_command(text: &str) -> Option<(&str, &str)> {
+    let text = text.strip_prefix(is_command_start)?;
+    let end = text
+        .find(|chr: char| !chr.is_ascii_alphanumeric() && chr != '_')
+        .unwrap_or(text.len());
+    if end == 0 {
+        None
+    } else {
+        Some((&text[..end], &text[end..]))
+    }
+}
+
+fn find_command(line: &str, name: &str) -> Option<usize> {
+    line.match_indices(is_command_start).find_map(|(pos, _)| {
+        let rest = &line[pos + 1..];
+        if rest.starts_with(name)
+            && !rest[name.len()..].starts_with(|chr: char| chr.is_ascii_alphanumeric())
+        {
+            Some(pos)
+        } else {
+            None
+        }
+    })
+}
+
+fn split_word(text: &str) -> (&str, &str) {
+    let text = text.trim_start();
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+    (&text[..end], text[end..].trim())
+}
+
+fn split_dir(text: &str) -> (Option<Direction>, &str) {
+    if let Some(rest) = text.strip_prefix('[') {
+        if let Some(end) = rest.find(']') {
+            let dir = rest[..end]
+                .split(',')
+                .map(str::trim)
+                .fold(None, |dir, part| match (dir, part) {
+                    (None, "in") => Some(Direction::In),
+                    (None, "out") => Some(Direction::Out),
+                    (None, "inout")
+                    | (Some(Direction::In), "out")
+                    | (Some(Direction::Out), "in") => Some(Direction::InOut),
+                    (dir, _) => dir,
+                });
+            return (dir, &rest[end + 1..]);
+        }
+    }
+    (None, text)
+}
+
+fn split_lang(text: &str) -> (Option<String>, &str) {
+    if let Some(rest) = text.strip_prefix('{') {
+        if let Some(end) = rest.find('}') {
+            let lang = rest[..end].trim().trim_start_matches('.');
+            return (
+                if lang.is_empty() {
+                    None
+                } else {
+                    Some(lang.into())
+                },
+                &rest[end + 1..],
+            );
+        }
+    }
+    (None, text)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn not_a() {
+        assert!(Comment::parse("// abc").is_none());
+        assert!(Comment::parse("//// abc").is_none());
+        assert!(Comment::parse("/* abc */").is_none());
+        assert!(Comment::parse("/**/").is_none());
+        assert!(Comment::parse("/***** abc *****/").is_none());
+    }
+
+    #[test]
+    fn line() {
+        let doc = Comment::parse("/// abc de").unwrap();
+        assert_eq!(doc.style, Style::Line);
+        assert!(!doc.trailing);
+        assert_eq!(doc.text, "abc de");
+
+        let doc = Comment::parse("//!abc de ").unwrap();
+        assert_eq!(doc.style, Style::QtLine);
+        assert_eq!(doc.text, "abc de");
+    }
+
+    #[test]
+    fn trailing() {
+        let doc = Comment::parse("///< abc de").unwrap();
+        assert_eq!(doc.style, Style::Line);
+        assert!(doc.trailing);
+        assert_eq!(doc.text, "abc de");
+
+        let doc = Comment::parse("/**< abc de */").unwrap();
+        assert_eq!(doc.style, Style::JavaDoc);
+        assert!(doc.trailing);
+        assert_eq!(doc.text, "abc de");
+    }
+
+    #[test]
+    fn block_singleline() {
+        let doc = Comment::parse("/** abc de */").unwrap();
+        assert_eq!(doc.style, Style::JavaDoc);
+        assert_eq!(doc.text, "abc de");
+
+        let doc = Comment::parse("/*! abc de */").unwrap();
+        assert_eq!(doc.style, Style::Qt);
+        assert_eq!(doc.text, "abc de");
+    }
+
+    #[test]
+    fn block_gutter() {
+        let doc = Comment::parse(
+            r#"/**
+ * abc de
+ *
+ *   f gh
+ */"#,
+        )
+        .unwrap();
+        assert_eq!(doc.text, "abc de\n\n  f gh");
+    }
+
+    #[test]
+    fn block_no_gutter() {
+        let doc = Comment::parse(
+            r#"/*! abc
+    de
+      f gh
+ */"#,
+        )
+        .unwrap();
+        assert_eq!(doc.text, "abc\nde\n  f gh");
+    }
+
+    #[test]
+    fn commands() {
+        let doc = Doc::parse(
+            r#"@brief Sum numbers
+
+Adds two numbers
+with care.
+
+@param[in] a First number
+@param[in,out] b Second number
+  which is modified
+\param c Third
+@return Sum of numbers
+@retval 0 On failure
+@note Not thread-safe"#,
+        );
+
+        assert_eq!(
+            doc.blocks,
+            [
+                Block::Brief("Sum numbers".into()),
+                Block::Paragraph("Adds two numbers\nwith care.".into()),
+                Block::Param {
+                    name: "a".into(),
+                    dir: Some(Direction::In),
+                    text: "First number".into()
+                },
+                Block::Param {
+                    name: "b".into(),
+                    dir: Some(Direction::InOut),
+                    text: "Second number\nwhich is modified".into()
+                },
+                Block::Param {
+                    name: "c".into(),
+                    dir: None,
+                    text: "Third".into()
+                },
+                Block::Return("Sum of numbers".into()),
+                Block::RetVal {
+                    value: "0".into(),
+                    text: "On failure".into()
+                },
+                Block::Tag {
+                    name: "note".into(),
+                    text: "Not thread-safe".into()
+                },
+            ]
+        );
+
+        assert_eq!(doc.brief(), Some("Sum numbers"));
+        assert_eq!(doc.returns(), Some("Sum of numbers"));
+        assert_eq!(doc.params().count(), 3);
+    }
+
+    #[test]
+    fn code() {
+        let doc = Doc::parse(
+            r#"Usage:
+\code{.c}
+int a = sum(1, 2);
+
+if (a) {
+    return;
+}
+\endcode
+Done with @c sum"#,
+        );
+
+        assert_eq!(
+            doc.blocks,
+            [
+                Block::Paragraph("Usage:".into()),
+                Block::Code {
+                    lang: Some("c".into()),
+                    text: "int a = sum(1, 2);\n\nif (a) {\n    return;\n}".into()
+                },
+                Block::Paragraph("Done with @c sum".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn javadoc() {
+        let doc = Comment::parse(
+            r#"/**
+ * Open file.
+ *
+ * @param path File path
+ * @return File descriptor
+ */"#,
+        )
+        .unwrap()
+        .doc();
+
+        assert_eq!(doc.brief(), Some("Open file."));
+        assert_eq!(
+            doc.params().collect::<Vec<_>>(),
+            [("path", None, "File path")]
+        );
+        assert_eq!(doc.returns(), Some("File descriptor"));
+    }
+}
@@ -0,0 +1,978 @@
+use super::Comment;
+use crate::{directive, Keyword, Lexeme, Lexer, Location, Token};
+use core::ops::Range;
+
+/// Kind of documented declaration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Kind {
+    /// Macro `#define`
+    Macro,
+    /// Function prototype or definition
+    Function,
+    /// Global variable
+    Variable,
+    /// Struct or union field
+    Field,
+    /// Enum constant
+    Constant,
+    /// Typedef or struct, union, enum definition
+    Type,
+}
+
+/// Documentation comment attached to declaration
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Attached<'l> {
+    /// Documentation comment
+    ///
+    /// Consecutive line comments are merged into single one.
+    pub comment: Comment,
+    /// Comment span in source
+    pub comment_span: Range<usize>,
+    /// Declaration kind
+    pub kind: Kind,
+    /// Declaration span in source
+    pub span: Range<usize>,
+    /// Declared name
+    pub name: Option<&'l str>,
+}
+
+/// Attach documentation comments to declarations
+///
+/// Leading comments are attached to the following declaration unless separated by blank line.
+/// Trailing comments (`///<`, `/**<`) are attached to the preceding declaration in the same
+/// scope.
+pub fn attach(source: &str) -> Vec<Attached<'_>> {
+    declarations(source)
+        .into_iter()
+        .flat_map(|decl| {
+            decl.comments.into_iter().map(move |doc| Attached {
+                comment: doc.comment,
+                comment_span: doc.span,
+                kind: decl.kind,
+                span: decl.span.clone(),
+                name: decl.name,
+            })
+        })
+        .collect()
+}
+
+/// Find top-level declarations, struct fields and enum constants
+///
+/// Function bodies and initializers are skipped.
+pub(crate) fn declarations(source: &str) -> Vec<Decl<'_>> {
+    declarations_in(source, &Lexer::from(source).collect::<Vec<_>>())
+}
+
+/// Find declarations using lexemes of source
+pub(crate) fn declarations_in<'l>(source: &'l str, lexemes: &[Lexeme<'l>]) -> Vec<Decl<'l>> {
+    let mut state = State {
+        source,
+        lexemes,
+        decls: Vec::new(),
+        scopes: vec![Scope::new(ScopeKind::Top, None)],
+    };
+
+    let mut index = 0;
+    while index < lexemes.len() {
+        index = state.step(index);
+    }
+
+    state.decls
+}
+
+pub(crate) struct Doc {
+    pub comment: Comment,
+    pub span: Range<usize>,
+}
+
+/// Struct, union or enum tag
+#[derive(Clone, Copy)]
+pub(crate) struct Tag<'l> {
+    /// `struct`, `union` or `enum`
+    pub keyword: Keyword,
+    pub name: &'l str,
+    pub location: Location,
+    /// Tag is followed by body
+    pub defined: bool,
+}
+
+pub(crate) struct Decl<'l> {
+    pub kind: Kind,
+    pub span: Range<usize>,
+    pub name: Option<&'l str>,
+    /// Location of declared name
+    pub location: Option<Location>,
+    /// Names declared by further comma separated declarators
+    pub others: Vec<(&'l str, Location)>,
+    /// Tag referenced or defined by declaration
+    pub tag: Option<Tag<'l>>,
+    /// Enclosing struct, union or enum (`None` for anonymous ones)
+    pub parent: Option<(Keyword, &'l str)>,
+    /// Function definition with body
+    pub body: bool,
+    /// Declared with `typedef`
+    pub typedef: bool,
+    /// Declared with `extern`
+    pub external: bool,
+    pub comments: Vec<Doc>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ScopeKind {
+    Top,
+    Aggregate,
+    Enum,
+}
+
+struct Item {
+    start: usize,
+    end: usize,
+    /// Lexeme indexes (`None` stands for nested body)
+    tokens: Vec<Option<usize>>,
+    /// Parentheses and brackets depth
+    depth: usize,
+    comments: Vec<Doc>,
+}
+
+struct Scope<'l> {
+    kind: ScopeKind,
+    /// Struct, union or enum tag
+    tag: Option<(Keyword, &'l str)>,
+    item: Option<Item>,
+    /// Last completed declaration
+    last: Option<usize>,
+    /// Pending leading comment
+    pending: Option<Doc>,
+}
+
+impl<'l> Scope<'l> {
+    fn new(kind: ScopeKind, tag: Option<(Keyword, &'l str)>) -> Self {
+        Self {
+            kind,
+            tag,
+            item: None,
+            last: None,
+            pending: None,
+        }
+    }
+}
+
+struct State<'s, 'l> {
+    source: &'l str,
+    lexemes: &'s [Lexeme<'l>],
+    decls: Vec<Decl<'l>>,
+    scopes: Vec<Scope<'l>>,
+}
+
+fn is_symbol(lexeme: &Lexeme, symbols: &[&str]) -> bool {
+    lexeme.token == Token::Symbol && symbols.contains(&lexeme.slice)
+}
+
+/// Names of identifier list when tokens end with K&R style function declarator
+fn identifier_list<'l>(lexemes: &[Lexeme<'l>], tokens: &[Option<usize>]) -> Option<Vec<&'l str>> {
+    let mut iter = tokens
+        .iter()
+        .rev()
+        .map(|index| index.map(|index| &lexemes[index]));
+    if !matches!(iter.next(), Some(Some(lexeme)) if is_symbol(lexeme, &[")"])) {
+        return None;
+    }
+
+    let is_name = |lexeme: &Lexeme| lexeme.token == Token::Identifier && lexeme.keyword().is_none();
+    let mut names = Vec::new();
+    for position in 0.. {
+        match iter.next() {
+            Some(Some(lexeme)) if position % 2 == 0 && is_name(lexeme) => names.push(lexeme.slice),
+            Some(Some(lexeme)) if position % 2 == 1 && is_symbol(lexeme, &[","]) => {}
+            Some(Some(lexeme)) if position % 2 == 1 && is_symbol(lexeme, &["("]) => {
+                return iter
+                    .next()
+                    .filter(|name| matches!(name, Some(name) if is_name(name)))
+                    .map(|_| names);
+            }
+            _ => return None,
+        }
+    }
+    None
+}
+
+fn has_blank_line(text: &str) -> bool {
+    let mut lines = text.split('\n');
+    lines.next();
+    let count = lines.clone().count();
+    lines
+        .take(count.saturating_sub(1))
+        .any(|line| line.trim().is_empty())
+}
+
+impl<'s, 'l> State<'s, 'l> {
+    fn scope(&mut self) -> &mut Scope<'l> {
+        self.scopes.last_mut().unwrap()
+    }
+
+    fn step(&mut self, index: usize) -> usize {
+        let lexeme = &self.lexemes[index];
+
+        match lexeme.token {
+            Token::Comment => {
+                if let Some(comment) = lexeme.doc() {
+                    self.comment(Doc {
+                        comment,
+                        span: lexeme.span.clone(),
+                    });
+                }
+                index + 1
+            }
+            Token::Symbol
+                if lexeme.slice == "#"
+                    && directive::at_line_start(self.source, lexeme.span.start) =>
+            {
+                self.directive(index)
+            }
+            _ => self.code(index),
+        }
+    }
+
+    fn comment(&mut self, doc: Doc) {
+        let source = self.source;
+        if doc.comment.trailing {
+            let scope = self.scopes.last_mut().unwrap();
+            if let Some(item) = &mut scope.item {
+                item.comments.push(doc);
+            } else if let Some(last) = scope.last {
+                self.decls[last].comments.push(doc);
+            }
+            return;
+        }
+
+        let scope = self.scope();
+        if let Some(pending) = &mut scope.pending {
+            // merge consecutive line comments
+            if pending.comment.style == doc.comment.style
+                && doc.comment.style.is_line()
+                && source[pending.span.end..doc.span.start]
+                    .matches('\n')
+                    .count()
+                    == 1
+            {
+                pending.comment.text.push('\n');
+                pending.comment.text.push_str(&doc.comment.text);
+                pending.span.end = doc.span.end;
+                return;
+            }
+        }
+        scope.pending = Some(doc);
+    }
+
+    /// Take pending comment which is not detached by blank line
+    fn take_pending(&mut self, pos: usize) -> Option<Doc> {
+        let source = self.source;
+        self.scope()
+            .pending
+            .take()
+            .filter(|doc| !has_blank_line(&source[doc.span.end..pos]))
+    }
+
+    fn directive(&mut self, index: usize) -> usize {
+        let start = self.lexemes[index].span.start;
+        let end = directive::end(self.source, start);
+        let next = self.lexemes[index..]
+            .iter()
+            .position(|lexeme| lexeme.span.start >= end)
+            .map(|offset| index + offset)
+            .unwrap_or(self.lexemes.len());
+        let lexemes = &self.lexemes[index + 1..next];
+
+        match lexemes.first().map(|lexeme| lexeme.slice) {
+            Some("define") => {
+                let comments = self.take_pending(start).into_iter().collect::<Vec<_>>();
+                let name = lexemes
+                    .get(1)
+                    .filter(|lexeme| lexeme.token == Token::Identifier);
+                let code_end = lexemes
+                    .iter()
+                    .rev()
+                    .find(|lexeme| lexeme.token != Token::Comment)
+                    .map(|lexeme| lexeme.span.end)
+                    .unwrap_or(self.lexemes[index].span.end);
+                self.push(Decl {
+                    kind: Kind::Macro,
+                    span: start..code_end,
+                    name: name.map(|lexeme| lexeme.slice),
+                    location: name.map(|lexeme| lexeme.location),
+                    others: Vec::new(),
+                    tag: None,
+                    parent: self.scopes.last().unwrap().tag,
+                    body: false,
+                    typedef: false,
+                    external: false,
+                    comments,
+                });
+                for lexeme in lexemes {
+                    if let Some(comment) = lexeme.doc().filter(|comment| comment.trailing) {
+                        self.comment(Doc {
+                            comment,
+                            span: lexeme.span.clone(),
+                        });
+                    }
+                }
+            }
+            Some("if" | "ifdef" | "ifndef" | "elif" | "else" | "endif") => {}
+            _ => self.scope().pending = None,
+        }
+
+        next
+    }
+
+    fn push(&mut self, decl: Decl<'l>) {
+        self.decls.push(decl);
+        let last = self.decls.len() - 1;
+        self.scope().last = Some(last);
+    }
+
+    fn code(&mut self, index: usize) -> usize {
+        let lexemes = self.lexemes;
+        let lexeme = &lexemes[index];
+        let kind = self.scope().kind;
+
+        if is_symbol(lexeme, &["}", "%>"]) {
+            if kind != ScopeKind::Top {
+                self.complete();
+                self.scopes.pop();
+                if let Some(item) = &mut self.scope().item {
+                    item.tokens.push(None);
+                    item.end = lexeme.span.end;
+                }
+            }
+            return index + 1;
+        }
+
+        if self.scope().item.is_none() {
+            let comments = self.take_pending(lexeme.span.start).into_iter().collect();
+            self.scope().item = Some(Item {
+                start: lexeme.span.start,
+                end: lexeme.span.end,
+                tokens: Vec::new(),
+                depth: 0,
+                comments,
+            });
+        }
+
+        // `int f(a, b) int a; int b; {` (parameter declarations are skipped)
+        if kind == ScopeKind::Top && lexeme.token == Token::Identifier {
+            if let Some(body) = self.knr_body(index) {
+                let item = self.scope().item.as_mut().unwrap();
+                item.end = lexemes[body - 1].span.end;
+                return body;
+            }
+        }
+
+        let item = self.scope().item.as_mut().unwrap();
+
+        if is_symbol(lexeme, &["{", "<%"]) {
+            return self.open(index);
+        }
+
+        if item.depth == 0 && is_symbol(lexeme, &[";"])
+            || kind == ScopeKind::Enum && item.depth == 0 && is_symbol(lexeme, &[","])
+        {
+            if lexeme.slice == ";" {
+                item.end = lexeme.span.end;
+            }
+            self.complete();
+            return index + 1;
+        }
+
+        if is_symbol(lexeme, &["(", "[", "<:"]) {
+            item.depth += 1;
+        } else if is_symbol(lexeme, &[")", "]", ":>"]) {
+            item.depth = item.depth.saturating_sub(1);
+        }
+
+        item.tokens.push(Some(index));
+        item.end = lexeme.span.end;
+        index + 1
+    }
+
+    /// Find body of K&R function definition with parameter declarations starting at index
+    ///
+    /// Each declaration should start with specifier, qualifier or known typedef name and declare
+    /// names from identifier list only.
+    fn knr_body(&self, index: usize) -> Option<usize> {
+        let lexemes = self.lexemes;
+        let item = self.scopes.last()?.item.as_ref()?;
+        if item.depth != 0 {
+            return None;
+        }
+        let names = identifier_list(lexemes, &item.tokens)?;
+
+        let mut declaration = Vec::new();
+        let mut depth = 0usize;
+        for (offset, lexeme) in lexemes[index..].iter().enumerate() {
+            if lexeme.token == Token::Comment {
+                continue;
+            }
+            if declaration.is_empty() {
+                if is_symbol(lexeme, &["{", "<%"]) {
+                    return Some(index + offset);
+                }
+                if !self.starts_declaration(lexeme) {
+                    return None;
+                }
+            }
+            if is_symbol(lexeme, &["{", "<%", "}", "%>", "#", "%:"]) {
+                return None;
+            }
+            if is_symbol(lexeme, &["(", "[", "<:"]) {
+                depth += 1;
+            } else if is_symbol(lexeme, &[")", "]", ":>"]) {
+                depth = depth.saturating_sub(1);
+            } else if depth == 0 && is_symbol(lexeme, &[";"]) {
+                let declared = declarators(lexemes, &declaration).all(|tokens| {
+                    declarator(lexemes, tokens)
+                        .name
+                        .map(|name| names.contains(&lexemes[name].slice))
+                        .unwrap_or(false)
+                });
+                if !declared {
+                    return None;
+                }
+                declaration.clear();
+                continue;
+            }
+            declaration.push(Some(index + offset));
+        }
+
+        None
+    }
+
+    /// Lexeme may start parameter declaration
+    fn starts_declaration(&self, lexeme: &Lexeme) -> bool {
+        use Keyword::*;
+        match lexeme.keyword() {
+            Some(
+                Auto | Register | Static | Extern | Char | Short | Int | Long | Signed | Unsigned
+                | Float | Double | Void | Bool | Complex | Imaginary | Const | Volatile | Restrict
+                | Atomic | Struct | Union | Enum,
+            ) => true,
+            Some(_) => false,
+            None => {
+                lexeme.token == Token::Identifier
+                    && self.decls.iter().any(|decl| {
+                        decl.typedef
+                            && (decl.name == Some(lexeme.slice)
+                                || decl.others.iter().any(|(name, _)| *name == lexeme.slice))
+                    })
+            }
+        }
+    }
+
+    fn open(&mut self, index: usize) -> usize {
+        let lexemes = self.lexemes;
+        let item = self.scope().item.as_mut().unwrap();
+        let tokens = item
+            .tokens
+            .iter()
+            .filter_map(|index| index.map(|index| &lexemes[index]))
+            .collect::<Vec<_>>();
+
+        // tag keyword optionally followed by tag name
+        let tagged = |keywords: &[Keyword]| {
+            let mut iter = tokens.iter().rev();
+            let last = iter.next();
+            let is_tag = |lexeme: &&&Lexeme| {
+                lexeme
+                    .keyword()
+                    .map(|keyword| keywords.contains(&keyword))
+                    .unwrap_or(false)
+            };
+            last.filter(is_tag).is_some()
+                || last
+                    .filter(|lexeme| lexeme.token == Token::Identifier)
+                    .is_some()
+                    && iter.next().filter(is_tag).is_some()
+        };
+
+        let tag = || match tokens.as_slice() {
+            [.., keyword, name] if name.token == Token::Identifier && name.keyword().is_none() => {
+                keyword.keyword().map(|keyword| (keyword, name.slice))
+            }
+            _ => None,
+        };
+
+        if tagged(&[Keyword::Struct, Keyword::Union]) {
+            self.scopes.push(Scope::new(ScopeKind::Aggregate, tag()));
+            return index + 1;
+        }
+        if tagged(&[Keyword::Enum]) {
+            self.scopes.push(Scope::new(ScopeKind::Enum, tag()));
+            return index + 1;
+        }
+
+        // `extern "C" {`
+        if matches!(tokens.as_slice(), [first, second] if first.slice == "extern" && second.token == Token::String)
+        {
+            self.scope().item = None;
+            return index + 1;
+        }
+
+        let is_function = tokens.iter().any(|lexeme| is_symbol(lexeme, &["("]))
+            && !tokens.iter().any(|lexeme| is_symbol(lexeme, &["="]));
+
+        let close = self.skip_balanced(index);
+        let item = self.scope().item.as_mut().unwrap();
+        item.tokens.push(None);
+        item.end = lexemes[close - 1].span.end;
+
+        if is_function && self.scope().kind == ScopeKind::Top {
+            self.complete_with(true);
+        }
+
+        close
+    }
+
+    /// Skip balanced braces group and return index after closing brace
+    fn skip_balanced(&self, index: usize) -> usize {
+        let mut depth = 0usize;
+        for (offset, lexeme) in self.lexemes[index..].iter().enumerate() {
+            if is_symbol(lexeme, &["{", "<%"]) {
+                depth += 1;
+            } else if is_symbol(lexeme, &["}", "%>"]) {
+                depth -= 1;
+                if depth == 0 {
+                    return index + offset + 1;
+                }
+            }
+        }
+        self.lexemes.len()
+    }
+
+    fn complete(&mut self) {
+        self.complete_with(false);
+    }
+
+    fn complete_with(&mut self, body: bool) {
+        let lexemes = self.lexemes;
+        let scope = self.scopes.last_mut().unwrap();
+        let item = if let Some(item) = scope.item.take() {
+            item
+        } else {
+            return;
+        };
+
+        let others = if scope.kind == ScopeKind::Enum {
+            Vec::new()
+        } else {
+            declarators(lexemes, &item.tokens)
+                .skip(1)
+                .filter_map(|tokens| declarator(lexemes, tokens).name)
+                .map(|index| (lexemes[index].slice, lexemes[index].location))
+                .collect()
+        };
+        let declarator = declarator(lexemes, &item.tokens);
+
+        let (kind, name) = match (scope.kind, &declarator) {
+            (ScopeKind::Enum, declarator) => (Kind::Constant, declarator.name),
+            (
+                _,
+                Declarator {
+                    typedef: true,
+                    name,
+                    ..
+                },
+            ) => (Kind::Type, *name),
+            (
+                _,
+                Declarator {
+                    name: Some(name),
+                    function: true,
+                    ..
+                },
+            ) => (Kind::Function, Some(*name)),
+            (
+                ScopeKind::Top,
+                Declarator {
+                    name: Some(name), ..
+                },
+            ) => (Kind::Variable, Some(*name)),
+            (
+                ScopeKind::Aggregate,
+                Declarator {
+                    name: Some(name), ..
+                },
+            ) => (Kind::Field, Some(*name)),
+            (_, Declarator { tag, .. }) => (Kind::Type, *tag),
+        };
+
+        let tag = declarator.tag.and_then(|index| {
+            let position = item.tokens.iter().position(|token| *token == Some(index))?;
+            Some(Tag {
+                keyword: declarator.tag_keyword?,
+                name: lexemes[index].slice,
+                location: lexemes[index].location,
+                defined: item.tokens.get(position + 1) == Some(&None),
+            })
+        });
+        let external = item
+            .tokens
+            .iter()
+            .flatten()
+            .any(|index| lexemes[*index].keyword() == Some(Keyword::Extern));
+        let parent = scope.tag;
+
+        self.push(Decl {
+            kind,
+            span: item.start..item.end,
+            name: name.map(|index| lexemes[index].slice),
+            location: name.map(|index| lexemes[index].location),
+            others,
+            tag,
+            parent,
+            body,
+            typedef: declarator.typedef,
+            external,
+            comments: item.comments,
+        });
+    }
+}
+
+#[derive(Default)]
+struct Declarator {
+    /// Declared name
+    name: Option<usize>,
+    /// Struct, union or enum tag
+    tag: Option<usize>,
+    /// Keyword preceding tag
+    tag_keyword: Option<Keyword>,
+    /// Declares function
+    function: bool,
+    /// Declares type
+    typedef: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Prev {
+    None,
+    Name,
+    Skip,
+    Close,
+    Other,
+}
+
+fn is_attribute(name: &str) -> bool {
+    matches!(
+        name,
+        "__attribute__" | "__attribute" | "__declspec" | "__asm__" | "__asm" | "asm"
+    )
+}
+
+/// Split tokens into declarators separated by top-level commas
+fn declarators<'t>(
+    lexemes: &'t [Lexeme],
+    tokens: &'t [Option<usize>],
+) -> impl Iterator<Item = &'t [Option<usize>]> {
+    let mut depth = 0usize;
+    tokens.split(move |index| {
+        let Some(index) = index else {
+            return false;
+        };
+        let lexeme = &lexemes[*index];
+        if is_symbol(lexeme, &["(", "[", "<:"]) {
+            depth += 1;
+        } else if is_symbol(lexeme, &[")", "]", ":>"]) {
+            depth = depth.saturating_sub(1);
+        }
+        depth == 0 && is_symbol(lexeme, &[","])
+    })
+}
+
+/// Find name declared by lexemes using tokens only
+pub(crate) fn declared_name(lexemes: &[Lexeme], tokens: &[usize]) -> Option<usize> {
+    let tokens = tokens.iter().copied().map(Some).collect::<Vec<_>>();
+    declarator(lexemes, &tokens).name
+}
+
+/// Find declared name using tokens only
+fn declarator(lexemes: &[Lexeme], tokens: &[Option<usize>]) -> Declarator {
+    let mut declarator = Declarator::default();
+    let mut parens = Vec::new();
+    let mut prev = Prev::None;
+    let mut tag_keyword = None;
+
+    for (position, index) in tokens.iter().enumerate() {
+        let lexeme = if let Some(index) = index {
+            &lexemes[*index]
+        } else {
+            prev = Prev::Other;
+            tag_keyword = None;
+            continue;
+        };
+
+        match lexeme.token {
+            Token::Identifier => {
+                if let Some(keyword) = lexeme.keyword() {
+                    match keyword {
+                        Keyword::TypeDef => declarator.typedef = true,
+                        Keyword::Struct | Keyword::Union | Keyword::Enum => {
+                            tag_keyword = Some(keyword)
+                        }
+                        _ => {}
+                    }
+                    prev = Prev::Other;
+                    continue;
+                }
+                if tag_keyword.is_some() {
+                    declarator.tag = Some(index.unwrap());
+                    declarator.tag_keyword = tag_keyword.take();
+                    prev = Prev::Other;
+                    continue;
+                }
+                if is_attribute(lexeme.slice) {
+                    prev = Prev::Skip;
+                    continue;
+                }
+                prev = Prev::Name;
+                if declarator.name.is_some() || parens.iter().any(|params| *params) {
+                    continue;
+                }
+                let next = tokens
+                    .get(position + 1)
+                    .map(|index| index.map(|index| lexemes[index].slice));
+                match next {
+                    None => declarator.name = *index,
+                    Some(Some("(")) => {
+                        declarator.name = *index;
+                        declarator.function = true;
+                    }
+                    Some(Some(")" | "[" | "<:" | ";" | "," | "=" | ":")) => {
+                        declarator.name = *index
+                    }
+                    _ => {}
+                }
+            }
+            Token::Symbol if lexeme.slice == "(" => {
+                parens.push(matches!(prev, Prev::Name | Prev::Skip | Prev::Close));
+                prev = Prev::Other;
+            }
+            Token::Symbol if lexeme.slice == ")" => {
+                parens.pop();
+                prev = Prev::Close;
+            }
+            _ => prev = Prev::Other,
+        }
+        tag_keyword = None;
+    }
+
+    declarator
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn attached(source: &str) -> Vec<(String, Kind, Option<&str>, &str)> {
+        attach(source)
+            .into_iter()
+            .map(|attached| {
+                (
+                    attached.comment.text,
+                    attached.kind,
+                    attached.name,
+                    &source[attached.span],
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn function() {
+        assert_eq!(
+            attached(
+                r#"
+/** Open file */
+int open(const char *path, int flags);
+
+/// Close file
+/// descriptor
+void close(int fd) {
+    /** not attached */
+    int a;
+}
+
+/** detached */
+
+int (*handler)(int sig);
+"#
+            ),
+            [
+                (
+                    "Open file".into(),
+                    Kind::Function,
+                    Some("open"),
+                    "int open(const char *path, int flags);"
+                ),
+                (
+                    "Close file\ndescriptor".into(),
+                    Kind::Function,
+                    Some("close"),
+                    "void close(int fd) {\n    /** not attached */\n    int a;\n}"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn variable() {
+        assert_eq!(
+            attached(
+                r#"
+/** Counter */
+static unsigned long counter = 0;
+/** Handler */
+void (*handler)(int sig);
+/** Names */
+const char *names[] = { "a", "b" };
+"#
+            ),
+            [
+                (
+                    "Counter".into(),
+                    Kind::Variable,
+                    Some("counter"),
+                    "static unsigned long counter = 0;"
+                ),
+                (
+                    "Handler".into(),
+                    Kind::Variable,
+                    Some("handler"),
+                    "void (*handler)(int sig);"
+                ),
+                (
+                    "Names".into(),
+                    Kind::Variable,
+                    Some("names"),
+                    "const char *names[] = { \"a\", \"b\" };"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn aggregate() {
+        assert_eq!(
+            attached(
+                r#"
+/** Point */
+typedef struct {
+    int x; ///< X coord
+    /** Y coord */
+    int y;
+    unsigned flags: 3; /**< Flags */
+} point_t;
+"#
+            ),
+            [
+                ("X coord".into(), Kind::Field, Some("x"), "int x;"),
+                ("Y coord".into(), Kind::Field, Some("y"), "int y;"),
+                ("Flags".into(), Kind::Field, Some("flags"), "unsigned flags: 3;"),
+                (
+                    "Point".into(),
+                    Kind::Type,
+                    Some("point_t"),
+                    "typedef struct {\n    int x; ///< X coord\n    /** Y coord */\n    int y;\n    unsigned flags: 3; /**< Flags */\n} point_t;"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn enumeration() {
+        assert_eq!(
+            attached(
+                r#"
+/** Color */
+enum color {
+    /** Red */
+    RED = 1,
+    GREEN = COLOR(1, 2), ///< Green
+    BLUE ///< Blue
+};
+"#
+            ),
+            [
+                ("Red".into(), Kind::Constant, Some("RED"), "RED = 1"),
+                (
+                    "Green".into(),
+                    Kind::Constant,
+                    Some("GREEN"),
+                    "GREEN = COLOR(1, 2)"
+                ),
+                ("Blue".into(), Kind::Constant, Some("BLUE"), "BLUE"),
+                (
+                    "Color".into(),
+                    Kind::Type,
+                    Some("color"),
+                    "enum color {\n    /** Red */\n    RED = 1,\n    GREEN = COLOR(1, 2), ///< Green\n    BLUE ///< Blue\n};"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn macros() {
+        assert_eq!(
+            attached(
+                r#"
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/** Max size */
+#define MAX_SIZE 16
+#define MIN_SIZE 1 ///< Min size
+/** Square */
+#define SQR(x) \
+    ((x) * (x))
+
+/** Include */
+#include <stdio.h>
+
+/** Init */
+int init(void);
+
+#ifdef __cplusplus
+}
+#endif
+"#
+            ),
+            [
+                (
+                    "Max size".into(),
+                    Kind::Macro,
+                    Some("MAX_SIZE"),
+                    "#define MAX_SIZE 16"
+                ),
+                (
+                    "Min size".into(),
+                    Kind::Macro,
+                    Some("MIN_SIZE"),
+                    "#define MIN_SIZE 1"
+                ),
+                (
+                    "Square".into(),
+                    Kind::Macro,
+                    Some("SQR"),
+                    "#define SQR(x) \\\n    ((x) * (x))"
+                ),
+                (
+                    "Init".into(),
+                    Kind::Function,
+                    Some("init"),
+                    "int init(void);"
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! Source encoding decoding
+//!
+//! Legacy sources may be encoded in Latin-1 or Windows-1252, or in UTF-16 with byte order
+//! mark. Decoded text may be lexed as usual, but note that lexeme spans refer to decoded text
+//! instead of original bytes. Use [`ByteLexer`](crate::ByteLexer) to keep original offsets.
+
+use std::borrow::Cow;
+
+/// Source encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Encoding {
+    /// UTF-8 (invalid sequences are replaced)
+    Utf8,
+    /// ISO-8859-1
+    Latin1,
+    /// Windows-1252 (superset of printable Latin-1)
+    Windows1252,
+    /// UTF-16 little endian
+    Utf16Le,
+    /// UTF-16 big endian
+    Utf16Be,
+}
+
+/// Windows-1252 characters of bytes `0x80..=0x9F` (undefined ones are mapped as in Latin-1)
+const WINDOWS_1252: [char; 32] = [
+    '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2C6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}', '\u{8F}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}', '\u{17E}', '\u{178}',
+];
+
+impl core::str::FromStr for Encoding {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" => Self::Utf8,
+            "latin1" | "latin-1" | "iso-8859-1" | "iso8859-1" => Self::Latin1,
+            "windows-1252" | "cp1252" => Self::Windows1252,
+            "utf-16le" | "utf16le" => Self::Utf16Le,
+            "utf-16be" | "utf16be" => Self::Utf16Be,
+            _ => return Err(()),
+        })
+    }
+}
+
+impl core::fmt::Display for Encoding {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(match self {
+            Self::Utf8 => "utf-8",
+            Self::Latin1 => "latin1",
+            Self::Windows1252 => "windows-1252",
+            Self::Utf16Le => "utf-16le",
+            Self::Utf16Be => "utf-16be",
+        })
+    }
+}
+
+impl Encoding {
+    /// Detect encoding by byte order mark
+    pub fn detect(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [0xEF, 0xBB, 0xBF, ..] => Some(Self::Utf8),
+            [0xFF, 0xFE, ..] => Some(Self::Utf16Le),
+            [0xFE, 0xFF, ..] => Some(Self::Utf16Be),
+            _ => None,
+        }
+    }
+
+    /// Byte order mark
+    pub fn bom(self) -> &'static [u8] {
+        match self {
+            Self::Utf8 => b"\xEF\xBB\xBF",
+            Self::Utf16Le => b"\xFF\xFE",
+            Self::Utf16Be => b"\xFE\xFF",
+            Self::Latin1 | Self::Windows1252 => b"",
+        }
+    }
+
+    /// Decode bytes skipping byte order mark
+    ///
+    /// Invalid sequences and unpaired surrogates are replaced with `U+FFFD`.
+    pub fn decode(self, bytes: &[u8]) -> Cow<'_, str> {
+        let bytes = bytes.strip_prefix(self.bom()).unwrap_or(bytes);
+
+        match self {
+            Self::Utf8 => String::from_utf8_lossy(bytes),
+            Self::Latin1 | Self::Windows1252 if bytes.is_ascii() => {
+                Cow::Borrowed(core::str::from_utf8(bytes).unwrap())
+            }
+            Self::Latin1 => bytes.iter().map(|&byte| byte as char).collect(),
+            Self::Windows1252 => bytes
+                .iter()
+                .map(|&byte| match byte {
+                    0x80..=0x9F => WINDOWS_1252[byte as usize - 0x80],
+                    _ => byte as char,
+                })
+                .collect(),
+            Self::Utf16Le | Self::Utf16Be => {
+                let units = bytes.chunks(2).map(|pair| match (self, pair) {
+                    (Self::Utf16Le, &[low, high]) => u16::from_le_bytes([low, high]),
+                    (_, &[high, low]) => u16::from_be_bytes([high, low]),
+                    // odd trailing byte
+                    _ => 0xDC00,
+                });
+                char::decode_utf16(units)
+                    .map(|chr| chr.unwrap_or(char::REPLACEMENT_CHARACTER))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Decode source code
+///
+/// Encoding is detected by byte order mark, otherwise valid UTF-8 is borrowed as is and
+/// fallback encoding is used for the rest.
+pub fn decode(bytes: &[u8], fallback: Encoding) -> Cow<'_, str> {
+    if let Some(encoding) = Encoding::detect(bytes) {
+        encoding.decode(bytes)
+    } else if let Ok(text) = core::str::from_utf8(bytes) {
+        Cow::Borrowed(text)
+    } else {
+        fallback.decode(bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn single_byte() {
+        let bytes = b"/* caf\xe9 \x80 */";
+        assert_eq!(Encoding::Latin1.decode(bytes), "/* café \u{80} */");
+        assert_eq!(Encoding::Windows1252.decode(bytes), "/* café € */");
+        assert_eq!(decode(bytes, Encoding::Windows1252), "/* café € */");
+        assert_eq!(decode(bytes, Encoding::Utf8), "/* caf\u{FFFD} \u{FFFD} */");
+        assert!(matches!(
+            decode(b"int a;", Encoding::Latin1),
+            Cow::Borrowed("int a;")
+        ));
+    }
+
+    #[test]
+    fn utf16() {
+        let le = b"\xff\xfei\0n\0t\0 \0\x3d\xd8\x00\xde";
+        assert_eq!(Encoding::detect(le), Some(Encoding::Utf16Le));
+        assert_eq!(decode(le, Encoding::Latin1), "int 😀");
+        let be = b"\xfe\xff\0i\0n\0t\xd8\x3d";
+        assert_eq!(decode(be, Encoding::Latin1), "int\u{FFFD}");
+        assert_eq!(decode(b"\xef\xbb\xbfint", Encoding::Latin1), "int");
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!("CP1252".parse(), Ok(Encoding::Windows1252));
+        assert_eq!("iso-8859-1".parse(), Ok(Encoding::Latin1));
+        assert_eq!(Encoding::Utf16Be.to_string(), "utf-16be");
+        assert_eq!("shift-jis".parse::<Encoding>(), Err(()));
+    }
+}
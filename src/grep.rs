@@ -0,0 +1,296 @@
+//! Token sequence patterns
+//!
+//! Patterns are written as C code like `memcpy ( $x , $y , sizeof ( $z ) )` where
+//! `$name` matches non-empty balanced token sequence. Repeated wildcards must match
+//! same tokens, `$_` matches anything. Comments are ignored when matching.
+
+use crate::{Lexeme, Lexer, Location, Token};
+use core::ops::Range;
+
+/// Pattern element
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Element {
+    /// Exact token
+    Token {
+        /// Token kind
+        token: Token,
+        /// Token text
+        slice: String,
+    },
+    /// Wildcard with optional name
+    Wildcard(Option<String>),
+}
+
+/// Token sequence pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pattern {
+    /// Pattern elements
+    pub elements: Vec<Element>,
+}
+
+impl core::str::FromStr for Pattern {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let elements = Lexer::from(s)
+            .filter(|lexeme| lexeme.token != Token::Comment)
+            .map(|lexeme| {
+                if lexeme.token == Token::Unknown {
+                    Err(())
+                } else if let Some(name) = lexeme.slice.strip_prefix('$') {
+                    Ok(Element::Wildcard(match name {
+                        "" | "_" => None,
+                        name => Some(name.into()),
+                    }))
+                } else {
+                    Ok(Element::Token {
+                        token: lexeme.token,
+                        slice: lexeme.slice.into(),
+                    })
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if elements.is_empty() {
+            Err(())
+        } else {
+            Ok(Self { elements })
+        }
+    }
+}
+
+/// Pattern match
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Match {
+    /// Location of first token
+    pub location: Location,
+    /// Byte range in source code
+    pub span: Range<usize>,
+    /// Named wildcards with ranges in source code
+    pub bindings: Vec<(String, Range<usize>)>,
+}
+
+impl Pattern {
+    /// Find non-overlapping matches in lexemes
+    pub fn find(&self, lexemes: &[Lexeme]) -> Vec<Match> {
+        let lexemes = lexemes
+            .iter()
+            .filter(|lexeme| lexeme.token != Token::Comment)
+            .collect::<Vec<_>>();
+        let mut matches = Vec::new();
+        let mut start = 0;
+
+        while start < lexemes.len() {
+            let mut bindings = Vec::new();
+            if let Some(end) = self.match_at(&lexemes, 0, start, &mut bindings) {
+                matches.push(Match {
+                    location: lexemes[start].location,
+                    span: lexemes[start].span.start..lexemes[end - 1].span.end,
+                    bindings: bindings
+                        .into_iter()
+                        .map(|(name, range): (&str, Range<usize>)| {
+                            (
+                                name.into(),
+                                lexemes[range.start].span.start..lexemes[range.end - 1].span.end,
+                            )
+                        })
+                        .collect(),
+                });
+                start = end;
+            } else {
+                start += 1;
+            }
+        }
+
+        matches
+    }
+
+    /// Match elements starting from `element` at lexeme `index`, returns end index
+    fn match_at<'p>(
+        &'p self,
+        lexemes: &[&Lexeme],
+        element: usize,
+        index: usize,
+        bindings: &mut Vec<(&'p str, Range<usize>)>,
+    ) -> Option<usize> {
+        let Some(current) = self.elements.get(element) else {
+            return Some(index);
+        };
+
+        match current {
+            Element::Token { token, slice } => {
+                let lexeme = lexemes.get(index)?;
+                if lexeme.token == *token && lexeme.slice == slice {
+                    self.match_at(lexemes, element + 1, index + 1, bindings)
+                } else {
+                    None
+                }
+            }
+            Element::Wildcard(name) => {
+                let bound = name.as_deref().and_then(|name| {
+                    bindings
+                        .iter()
+                        .find(|(bound, _)| *bound == name)
+                        .map(|(_, range)| range.clone())
+                });
+
+                if let Some(bound) = bound {
+                    let end = index + bound.len();
+                    let same = end <= lexemes.len()
+                        && lexemes[bound]
+                            .iter()
+                            .zip(&lexemes[index..end])
+                            .all(|(a, b)| a.token == b.token && a.slice == b.slice);
+                    return if same {
+                        self.match_at(lexemes, element + 1, end, bindings)
+                    } else {
+                        None
+                    };
+                }
+
+                let mut depth = 0usize;
+                for end in index..lexemes.len() {
+                    let lexeme = lexemes[end];
+                    if lexeme.token == Token::Symbol {
+                        match lexeme.slice {
+                            "(" | "[" | "{" | "<%" | "<:" => depth += 1,
+                            ")" | "]" | "}" | "%>" | ":>" => {
+                                if depth == 0 {
+                                    return None;
+                                }
+                                depth -= 1;
+                            }
+                            ";" if depth == 0 => return None,
+                            _ => {}
+                        }
+                    }
+                    if depth > 0 {
+                        continue;
+                    }
+
+                    let len = bindings.len();
+                    if let Some(name) = name {
+                        bindings.push((name, index..end + 1));
+                    }
+                    if let Some(end) = self.match_at(lexemes, element + 1, end + 1, bindings) {
+                        return Some(end);
+                    }
+                    bindings.truncate(len);
+                }
+
+                None
+            }
+        }
+    }
+}
+
+/// Find pattern matches in source code
+pub fn search(source: &str, pattern: &Pattern) -> Vec<Match> {
+    pattern.find(&Lexer::from(source).collect::<Vec<_>>())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn find<'s>(source: &'s str, pattern: &str) -> Vec<(&'s str, Vec<(String, &'s str)>)> {
+        search(source, &pattern.parse().unwrap())
+            .into_iter()
+            .map(|found| {
+                (
+                    &source[found.span],
+                    found
+                        .bindings
+                        .into_iter()
+                        .map(|(name, span)| (name, &source[span]))
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pattern() {
+        assert_eq!(
+            "f($x, $_)".parse(),
+            Ok(Pattern {
+                elements: vec![
+                    Element::Token {
+                        token: Token::Identifier,
+                        slice: "f".into()
+                    },
+                    Element::Token {
+                        token: Token::Symbol,
+                        slice: "(".into()
+                    },
+                    Element::Wildcard(Some("x".into())),
+                    Element::Token {
+                        token: Token::Symbol,
+                        slice: ",".into()
+                    },
+                    Element::Wildcard(None),
+                    Element::Token {
+                        token: Token::Symbol,
+                        slice: ")".into()
+                    },
+                ]
+            })
+        );
+        assert_eq!("/* */".parse::<Pattern>(), Err(()));
+    }
+
+    #[test]
+    fn tokens() {
+        assert_eq!(
+            find(
+                "// malloc\nchar *s = \"malloc\"; p = malloc (10);",
+                "malloc("
+            ),
+            [("malloc (", vec![])]
+        );
+    }
+
+    #[test]
+    fn wildcards() {
+        assert_eq!(
+            find(
+                "memcpy(dst, f(a, b), sizeof(*dst)); memcpy(d, s, n);",
+                "memcpy ( $x , $y , sizeof ( $z ) )"
+            ),
+            [(
+                "memcpy(dst, f(a, b), sizeof(*dst))",
+                vec![
+                    ("x".into(), "dst"),
+                    ("y".into(), "f(a, b)"),
+                    ("z".into(), "*dst")
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn backreference() {
+        assert_eq!(
+            find("a = a + 1; b = c + 1;", "$x = $x + 1"),
+            [("a = a + 1", vec![("x".into(), "a")])]
+        );
+    }
+
+    #[test]
+    fn statement_boundary() {
+        assert_eq!(
+            find("x; y + 1", "$a + 1"),
+            [("y + 1", vec![("a".into(), "y")])]
+        );
+        assert_eq!(
+            find("free(p); use(p);", "free($x); $f($x)"),
+            [(
+                "free(p); use(p)",
+                vec![("x".into(), "p"), ("f".into(), "use")]
+            )]
+        );
+    }
+}
@@ -0,0 +1,421 @@
+//! Syntax highlighting
+//!
+//! Classifies tokens into highlighting classes and renders source code as HTML with CSS
+//! classes or as text with ANSI escape sequences.
+
+use crate::{directive, Keyword, Lexer, Token};
+use core::ops::Range;
+
+/// Highlighting class
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Class {
+    /// Keyword except type specifiers
+    Keyword,
+    /// Type specifier keyword or `_t` suffixed type name
+    Type,
+    /// Integer or floating-point literal
+    Number,
+    /// String literal or header name
+    String,
+    /// Character literal
+    Char,
+    /// Comment
+    Comment,
+    /// Preprocessor directive name
+    Preprocessor,
+    /// Punctuator
+    Punctuation,
+    /// Unknown token
+    Error,
+}
+
+impl Class {
+    /// CSS class name
+    pub fn css(&self) -> &'static str {
+        match self {
+            Self::Keyword => "kw",
+            Self::Type => "ty",
+            Self::Number => "num",
+            Self::String => "str",
+            Self::Char => "chr",
+            Self::Comment => "com",
+            Self::Preprocessor => "pp",
+            Self::Punctuation => "pun",
+            Self::Error => "err",
+        }
+    }
+
+    /// ANSI SGR parameters (empty for default style)
+    pub fn ansi(&self) -> &'static str {
+        match self {
+            Self::Keyword => "1;35",
+            Self::Type => "36",
+            Self::Number => "33",
+            Self::String | Self::Char => "32",
+            Self::Comment => "90",
+            Self::Preprocessor => "34",
+            Self::Punctuation => "",
+            Self::Error => "4;31",
+        }
+    }
+}
+
+/// Highlighted token
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    /// Highlighting class
+    pub class: Class,
+    /// Byte range in source code
+    pub range: Range<usize>,
+}
+
+/// Style sheet for HTML output
+pub const CSS: &str = "\
+pre.clex { background: #fafafa; color: #383a42; padding: 0.5em; }
+pre.clex .ln { color: #9d9d9f; display: inline-block; margin-right: 1em; text-align: right; \
+user-select: none; text-decoration: none; }
+pre.clex .kw { color: #a626a4; font-weight: bold; }
+pre.clex .ty { color: #0184bc; }
+pre.clex .num { color: #986801; }
+pre.clex .str, pre.clex .chr { color: #50a14f; }
+pre.clex .com { color: #a0a1a7; font-style: italic; }
+pre.clex .pp { color: #4078f2; }
+pre.clex .pun { color: #383a42; }
+pre.clex .err { color: #e45649; text-decoration: underline wavy; }
+";
+
+/// Rendering options
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Options {
+    /// Prefix line numbers
+    pub line_numbers: bool,
+    /// Add line anchors `{prefix}L{line}` (HTML only)
+    pub anchors: Option<String>,
+    /// Number of first line (for snippets)
+    pub first_line: u32,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            line_numbers: false,
+            anchors: None,
+            first_line: 1,
+        }
+    }
+}
+
+/// Classify tokens of source code
+pub fn spans(source: &str) -> Vec<Span> {
+    spans_lexer(Lexer::from(source))
+}
+
+/// Classify tokens using lexer (to use specific dialect)
+pub fn spans_lexer(lexer: Lexer) -> Vec<Span> {
+    let source = lexer.source();
+    let dialect = lexer.dialect();
+    let mut spans = Vec::new();
+    // end of current directive and whether it is an include
+    let mut directive: Option<(usize, bool)> = None;
+    // directive name is expected
+    let mut hash = false;
+
+    for lexeme in lexer {
+        if directive
+            .filter(|(end, _)| lexeme.span.start < *end)
+            .is_none()
+        {
+            directive = None;
+        }
+
+        let class = match lexeme.token {
+            Token::Comment => Class::Comment,
+            Token::Symbol
+                if matches!(lexeme.slice, "#" | "%:")
+                    && directive.is_none()
+                    && directive::at_line_start(source, lexeme.span.start) =>
+            {
+                directive = Some((directive::end(source, lexeme.span.start), false));
+                hash = true;
+                spans.push(Span {
+                    class: Class::Preprocessor,
+                    range: lexeme.span,
+                });
+                continue;
+            }
+            Token::Identifier if hash => {
+                let include = matches!(lexeme.slice, "include" | "include_next" | "import");
+                directive = directive.map(|(end, _)| (end, include));
+                Class::Preprocessor
+            }
+            _ if matches!(directive, Some((_, true))) => Class::String,
+            Token::Symbol => Class::Punctuation,
+            Token::Char => Class::Char,
+            Token::String => Class::String,
+            Token::Int | Token::Float => Class::Number,
+            Token::Identifier => match lexeme.keyword_in(dialect) {
+                Some(keyword) if is_type(keyword) => Class::Type,
+                Some(_) => Class::Keyword,
+                None if lexeme.slice.ends_with("_t") => Class::Type,
+                None => {
+                    hash = false;
+                    continue;
+                }
+            },
+            Token::Unknown => Class::Error,
+        };
+
+        if lexeme.token != Token::Comment {
+            hash = false;
+        }
+
+        spans.push(Span {
+            class,
+            range: lexeme.span,
+        });
+    }
+
+    spans
+}
+
+pub(crate) fn is_type(keyword: Keyword) -> bool {
+    use Keyword::*;
+    matches!(
+        keyword,
+        Void | Char
+            | Short
+            | Int
+            | Long
+            | Float
+            | Double
+            | Signed
+            | Unsigned
+            | Bool
+            | Complex
+            | Imaginary
+    )
+}
+
+/// Split source into lines of segments with optional class
+fn lines<'s>(source: &'s str, spans: &[Span]) -> Vec<Vec<(Option<Class>, &'s str)>> {
+    let mut lines = vec![Vec::new()];
+    let mut push = |class: Option<Class>, text: &'s str| {
+        for (index, part) in text.split('\n').enumerate() {
+            if index > 0 {
+                lines.push(Vec::new());
+            }
+            let part = part.trim_end_matches('\r');
+            if !part.is_empty() {
+                lines.last_mut().unwrap().push((class, part));
+            }
+        }
+    };
+
+    let mut pos = 0;
+    for span in spans {
+        push(None, &source[pos..span.range.start]);
+        push(Some(span.class), &source[span.range.clone()]);
+        pos = span.range.end;
+    }
+    push(None, &source[pos..]);
+
+    if source.ends_with('\n') {
+        lines.pop();
+    }
+
+    lines
+}
+
+/// Render source code as HTML `<pre>` block
+pub fn html(source: &str, options: &Options) -> String {
+    let lines = lines(source, &spans(source));
+    let width = (options.first_line as usize + lines.len())
+        .to_string()
+        .len();
+    let mut out = String::from("<pre class=\"clex\">");
+
+    for (index, segments) in lines.iter().enumerate() {
+        let number = options.first_line as usize + index;
+        match &options.anchors {
+            Some(prefix) => {
+                out.push_str(&format!("<span id=\"{}L{}\">", escape(prefix), number));
+                if options.line_numbers {
+                    out.push_str(&format!(
+                        "<a class=\"ln\" href=\"#{}L{}\" style=\"min-width: {}ch\">{}</a>",
+                        escape(prefix),
+                        number,
+                        width,
+                        number
+                    ));
+                }
+            }
+            None if options.line_numbers => out.push_str(&format!(
+                "<span class=\"ln\" style=\"min-width: {}ch\">{}</span>",
+                width, number
+            )),
+            None => {}
+        }
+
+        for (class, text) in segments {
+            match class {
+                Some(class) => out.push_str(&format!(
+                    "<span class=\"{}\">{}</span>",
+                    class.css(),
+                    escape(text)
+                )),
+                None => out.push_str(&escape(text)),
+            }
+        }
+
+        if options.anchors.is_some() {
+            out.push_str("</span>");
+        }
+        out.push('\n');
+    }
+
+    out.push_str("</pre>\n");
+    out
+}
+
+/// Standalone HTML document with style sheet
+pub fn document(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>\n{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape(title),
+        CSS,
+        body
+    )
+}
+
+/// Escape HTML special characters
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for chr in text.chars() {
+        match chr {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            chr => out.push(chr),
+        }
+    }
+    out
+}
+
+/// Render source code with ANSI escape sequences
+///
+/// Styles are reset at the end of every line.
+pub fn ansi(source: &str, options: &Options) -> String {
+    let lines = lines(source, &spans(source));
+    let width = (options.first_line as usize + lines.len())
+        .to_string()
+        .len();
+    let mut out = String::new();
+
+    for (index, segments) in lines.iter().enumerate() {
+        if options.line_numbers {
+            out.push_str(&format!(
+                "\x1b[90m{:>width$}\x1b[0m ",
+                options.first_line as usize + index,
+                width = width
+            ));
+        }
+
+        for (class, text) in segments {
+            match class
+                .map(|class| class.ansi())
+                .filter(|sgr| !sgr.is_empty())
+            {
+                Some(sgr) => out.push_str(&format!("\x1b[{}m{}\x1b[0m", sgr, text)),
+                None => out.push_str(text),
+            }
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn classes(source: &str) -> Vec<(&str, Class)> {
+        spans(source)
+            .into_iter()
+            .map(|span| (&source[span.range], span.class))
+            .collect()
+    }
+
+    #[test]
+    fn classify() {
+        assert_eq!(
+            classes("#include <stdio.h>\n# define N 1 // n\nstatic size_t f(void) { return 'a' + 0.5; }"),
+            [
+                ("#", Class::Preprocessor),
+                ("include", Class::Preprocessor),
+                ("<", Class::String),
+                ("stdio", Class::String),
+                (".", Class::String),
+                ("h", Class::String),
+                (">", Class::String),
+                ("#", Class::Preprocessor),
+                ("define", Class::Preprocessor),
+                ("1", Class::Number),
+                ("// n", Class::Comment),
+                ("static", Class::Keyword),
+                ("size_t", Class::Type),
+                ("(", Class::Punctuation),
+                ("void", Class::Type),
+                (")", Class::Punctuation),
+                ("{", Class::Punctuation),
+                ("return", Class::Keyword),
+                ("'a'", Class::Char),
+                ("+", Class::Punctuation),
+                ("0.5", Class::Number),
+                (";", Class::Punctuation),
+                ("}", Class::Punctuation),
+            ]
+        );
+    }
+
+    #[test]
+    fn html_lines() {
+        assert_eq!(
+            html(
+                "/* a\n<b> */\nint x;\n",
+                &Options {
+                    line_numbers: true,
+                    anchors: Some("f-".into()),
+                    first_line: 9,
+                }
+            ),
+            "<pre class=\"clex\">\
+             <span id=\"f-L9\"><a class=\"ln\" href=\"#f-L9\" style=\"min-width: 2ch\">9</a>\
+             <span class=\"com\">/* a</span></span>\n\
+             <span id=\"f-L10\"><a class=\"ln\" href=\"#f-L10\" style=\"min-width: 2ch\">10</a>\
+             <span class=\"com\">&lt;b&gt; */</span></span>\n\
+             <span id=\"f-L11\"><a class=\"ln\" href=\"#f-L11\" style=\"min-width: 2ch\">11</a>\
+             <span class=\"ty\">int</span> x<span class=\"pun\">;</span></span>\n\
+             </pre>\n"
+        );
+    }
+
+    #[test]
+    fn ansi_lines() {
+        assert_eq!(
+            ansi(
+                "int x; // c\n",
+                &Options {
+                    line_numbers: true,
+                    ..Options::default()
+                }
+            ),
+            "\x1b[90m1\x1b[0m \x1b[36mint\x1b[0m x; \x1b[90m// c\x1b[0m\n"
+        );
+    }
+}
@@ -0,0 +1,577 @@
+//! Gettext messages extraction
+//!
+//! Finds translatable strings in calls of keyword functions like `_("...")`,
+//! `ngettext(s, p, n)` or `pgettext(ctx, s)` and writes `.pot` catalogs.
+
+use std::collections::HashMap;
+
+use crate::{comment, Lexeme, Lexer, Location, Token};
+
+/// Keyword function specification
+///
+/// Arguments positions starts from 1 like in `xgettext --keyword`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Spec {
+    /// Function name
+    pub name: String,
+    /// Position of singular message argument
+    pub singular: usize,
+    /// Position of plural message argument
+    pub plural: Option<usize>,
+    /// Position of context argument
+    pub context: Option<usize>,
+}
+
+impl core::str::FromStr for Spec {
+    type Err = ();
+
+    /// Parse keyword spec
+    ///
+    /// Syntax: `NAME[:ARG[,ARG...]]` where `ARG` is singular, plural position or context
+    /// position with `c` suffix, e.g. `_`, `dgettext:2`, `ngettext:1,2`, `pgettext:1c,2`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, args) = s.split_once(':').unwrap_or((s, ""));
+
+        if name.is_empty() || !name.chars().all(|chr| chr.is_alphanumeric() || chr == '_') {
+            return Err(());
+        }
+
+        let mut spec = Self {
+            name: name.into(),
+            singular: 1,
+            plural: None,
+            context: None,
+        };
+        let mut positions = Vec::new();
+
+        for arg in args.split(',').filter(|arg| !arg.is_empty()) {
+            if let Some(pos) = arg.strip_suffix('c') {
+                spec.context = Some(pos.parse().map_err(|_| ())?);
+            } else {
+                positions.push(arg.parse().map_err(|_| ())?);
+            }
+        }
+
+        match positions[..] {
+            [] => {}
+            [singular] => spec.singular = singular,
+            [singular, plural] => {
+                spec.singular = singular;
+                spec.plural = Some(plural);
+            }
+            _ => return Err(()),
+        }
+
+        if spec.singular == 0 || spec.plural == Some(0) || spec.context == Some(0) {
+            return Err(());
+        }
+
+        Ok(spec)
+    }
+}
+
+/// Default keyword specs
+pub const DEFAULT_KEYWORDS: &[&str] = &[
+    "_",
+    "N_",
+    "gettext",
+    "gettext_noop",
+    "dgettext:2",
+    "dcgettext:2",
+    "ngettext:1,2",
+    "dngettext:2,3",
+    "dcngettext:2,3",
+    "pgettext:1c,2",
+    "dpgettext:2c,3",
+    "dcpgettext:2c,3",
+    "npgettext:1c,2,3",
+    "dnpgettext:2c,3,4",
+    "dcnpgettext:2c,3,4",
+];
+
+/// Extraction options
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Options {
+    /// Keyword functions
+    pub keywords: Vec<Spec>,
+    /// Tag of comments for translators (empty to take all comments, none to skip comments)
+    pub comment_tag: Option<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            keywords: DEFAULT_KEYWORDS
+                .iter()
+                .map(|spec| spec.parse().unwrap())
+                .collect(),
+            comment_tag: Some("TRANSLATORS:".into()),
+        }
+    }
+}
+
+/// Translatable message found in source code
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Message {
+    /// Message context (`msgctxt`)
+    pub context: Option<String>,
+    /// Singular message (`msgid`)
+    pub id: String,
+    /// Plural message (`msgid_plural`)
+    pub plural: Option<String>,
+    /// Comments for translators
+    pub comments: Vec<String>,
+    /// Location of keyword function call
+    pub location: Location,
+}
+
+/// Extract messages from source code
+pub fn extract(source: &str, options: &Options) -> Vec<Message> {
+    let mut messages = Vec::new();
+    let mut tokens = Vec::new();
+    // Translator comments and line of comment end
+    let mut comments: Vec<(String, u32)> = Vec::new();
+
+    for lexeme in Lexer::from(source) {
+        if lexeme.token == Token::Comment {
+            if let Some(text) = translator_comment(&lexeme, options) {
+                let end = lexeme.location.advance(lexeme.slice).line;
+                comments.push((text, end));
+            }
+        } else {
+            tokens.push(lexeme);
+        }
+    }
+
+    let mut index = 0;
+    while index < tokens.len() {
+        let lexeme = &tokens[index];
+        index += 1;
+
+        if lexeme.token != Token::Identifier
+            || tokens
+                .get(index)
+                .map(|next| next.slice != "(")
+                .unwrap_or(true)
+        {
+            continue;
+        }
+
+        let spec = if let Some(spec) = options
+            .keywords
+            .iter()
+            .find(|spec| spec.name == lexeme.slice)
+        {
+            spec
+        } else {
+            continue;
+        };
+
+        let args = call_args(&tokens[index + 1..]);
+        let arg = |pos: usize| args.get(pos - 1).and_then(|arg| string_arg(arg));
+
+        let id = if let Some(id) = arg(spec.singular) {
+            id
+        } else {
+            continue;
+        };
+
+        let plural = match spec.plural {
+            Some(pos) => match arg(pos) {
+                Some(plural) => Some(plural),
+                None => continue,
+            },
+            None => None,
+        };
+
+        let context = match spec.context {
+            Some(pos) => match arg(pos) {
+                Some(context) => Some(context),
+                None => continue,
+            },
+            None => None,
+        };
+
+        // comments which ends on the same or previous line
+        let line = lexeme.location.line;
+        let attached = comments
+            .iter()
+            .take_while(|(_, end)| *end <= line)
+            .filter(|(_, end)| *end + 1 >= line)
+            .count();
+        let taken = comments.iter().take_while(|(_, end)| *end <= line).count();
+        let comments = comments.drain(..taken).skip(taken - attached);
+
+        messages.push(Message {
+            context,
+            id,
+            plural,
+            comments: comments.map(|(text, _)| text).collect(),
+            location: lexeme.location,
+        });
+    }
+
+    messages
+}
+
+fn translator_comment(lexeme: &Lexeme, options: &Options) -> Option<String> {
+    let tag = options.comment_tag.as_deref()?;
+    let text = lexeme.comment_with(&comment::Options::clean())?.text;
+    let pos = text.find(tag)?;
+    Some(text[pos..].trim_end().into())
+}
+
+/// Split tokens of call arguments until closing parenthesis
+fn call_args<'a, 'l>(tokens: &'a [Lexeme<'l>]) -> Vec<&'a [Lexeme<'l>]> {
+    let mut args = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (index, lexeme) in tokens.iter().enumerate() {
+        if lexeme.token != Token::Symbol {
+            continue;
+        }
+        match lexeme.slice {
+            "(" | "[" | "{" => depth += 1,
+            ")" | "]" | "}" if depth > 0 => depth -= 1,
+            ")" => {
+                args.push(&tokens[start..index]);
+                break;
+            }
+            "," if depth == 0 => {
+                args.push(&tokens[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+
+    args
+}
+
+/// Concatenate string literals of argument
+fn string_arg(tokens: &[Lexeme]) -> Option<String> {
+    if tokens.is_empty() {
+        return None;
+    }
+    tokens
+        .iter()
+        .map(|lexeme| lexeme.string())
+        .collect::<Option<Vec<_>>>()
+        .map(|parts| parts.concat())
+}
+
+/// Source reference
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Reference {
+    /// Source file path
+    pub file: String,
+    /// Line number
+    pub line: u32,
+}
+
+/// Catalog entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Entry {
+    /// Message context (`msgctxt`)
+    pub context: Option<String>,
+    /// Singular message (`msgid`)
+    pub id: String,
+    /// Plural message (`msgid_plural`)
+    pub plural: Option<String>,
+    /// Comments for translators
+    pub comments: Vec<String>,
+    /// Source references
+    pub references: Vec<Reference>,
+}
+
+/// Messages catalog (template)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Catalog {
+    entries: Vec<Entry>,
+    /// Entry positions by context and id
+    #[cfg_attr(feature = "serde", serde(skip))]
+    index: HashMap<(Option<String>, String), usize>,
+}
+
+impl Catalog {
+    /// Entries in order of first appearance
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Add messages found in file
+    ///
+    /// Messages with same context and id are merged.
+    pub fn add(&mut self, file: &str, messages: impl IntoIterator<Item = Message>) {
+        for message in messages {
+            let reference = Reference {
+                file: file.into(),
+                line: message.location.line,
+            };
+
+            let key = (message.context, message.id);
+            if let Some(&position) = self.index.get(&key) {
+                let entry = &mut self.entries[position];
+                if entry.plural.is_none() {
+                    entry.plural = message.plural;
+                }
+                for comment in message.comments {
+                    if !entry.comments.contains(&comment) {
+                        entry.comments.push(comment);
+                    }
+                }
+                entry.references.push(reference);
+            } else {
+                self.index.insert(key.clone(), self.entries.len());
+                let (context, id) = key;
+                self.entries.push(Entry {
+                    context,
+                    id,
+                    plural: message.plural,
+                    comments: message.comments,
+                    references: vec![reference],
+                });
+            }
+        }
+    }
+}
+
+impl core::fmt::Display for Catalog {
+    /// Format catalog as `.pot` file
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        writeln!(f, "# SOME DESCRIPTIVE TITLE.")?;
+        writeln!(f, "# Copyright (C) YEAR THE PACKAGE'S COPYRIGHT HOLDER")?;
+        writeln!(
+            f,
+            "# This file is distributed under the same license as the PACKAGE package."
+        )?;
+        writeln!(f, "# FIRST AUTHOR <EMAIL@ADDRESS>, YEAR.")?;
+        writeln!(f, "#")?;
+        writeln!(f, "#, fuzzy")?;
+        writeln!(f, "msgid \"\"")?;
+        writeln!(f, "msgstr \"\"")?;
+        for header in [
+            "Project-Id-Version: PACKAGE VERSION",
+            "Report-Msgid-Bugs-To: ",
+            "PO-Revision-Date: YEAR-MO-DA HO:MI+ZONE",
+            "Last-Translator: FULL NAME <EMAIL@ADDRESS>",
+            "Language-Team: LANGUAGE <LL@li.org>",
+            "Language: ",
+            "MIME-Version: 1.0",
+            "Content-Type: text/plain; charset=UTF-8",
+            "Content-Transfer-Encoding: 8bit",
+        ] {
+            writeln!(f, "\"{}\\n\"", header)?;
+        }
+        if self.entries.iter().any(|entry| entry.plural.is_some()) {
+            writeln!(
+                f,
+                "\"Plural-Forms: nplurals=INTEGER; plural=EXPRESSION;\\n\""
+            )?;
+        }
+
+        for entry in &self.entries {
+            writeln!(f)?;
+            for comment in &entry.comments {
+                for line in comment.lines() {
+                    writeln!(f, "#. {}", line)?;
+                }
+            }
+            if !entry.references.is_empty() {
+                write!(f, "#:")?;
+                for reference in &entry.references {
+                    write!(f, " {}:{}", reference.file, reference.line)?;
+                }
+                writeln!(f)?;
+            }
+            if let Some(context) = &entry.context {
+                writeln!(f, "msgctxt {}", po_string(context))?;
+            }
+            writeln!(f, "msgid {}", po_string(&entry.id))?;
+            if let Some(plural) = &entry.plural {
+                writeln!(f, "msgid_plural {}", po_string(plural))?;
+                writeln!(f, "msgstr[0] \"\"")?;
+                writeln!(f, "msgstr[1] \"\"")?;
+            } else {
+                writeln!(f, "msgstr \"\"")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Quote string for PO file splitting it by new lines
+fn po_string(text: &str) -> String {
+    let escape = |text: &str| {
+        let mut out = String::with_capacity(text.len() + 2);
+        out.push('"');
+        for chr in text.chars() {
+            match chr {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                '\n' => out.push_str("\\n"),
+                '\t' => out.push_str("\\t"),
+                '\r' => out.push_str("\\r"),
+                '\x07' => out.push_str("\\a"),
+                '\x08' => out.push_str("\\b"),
+                '\x0b' => out.push_str("\\v"),
+                '\x0c' => out.push_str("\\f"),
+                // other control characters as octal escapes like xgettext
+                chr if chr.is_ascii_control() => {
+                    out.push_str(&format!("\\{:03o}", chr as u32));
+                }
+                chr => out.push(chr),
+            }
+        }
+        out.push('"');
+        out
+    };
+
+    let lines = text.split_inclusive('\n').collect::<Vec<_>>();
+    if lines.len() > 1 {
+        let mut out = String::from("\"\"");
+        for line in lines {
+            out.push('\n');
+            out.push_str(&escape(line));
+        }
+        out
+    } else {
+        escape(text)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn spec() {
+        assert_eq!(
+            "npgettext:1c,2,3".parse(),
+            Ok(Spec {
+                name: "npgettext".into(),
+                singular: 2,
+                plural: Some(3),
+                context: Some(1),
+            })
+        );
+        assert_eq!(
+            "_".parse(),
+            Ok(Spec {
+                name: "_".into(),
+                singular: 1,
+                plural: None,
+                context: None,
+            })
+        );
+        assert_eq!("f:0".parse::<Spec>(), Err(()));
+        assert_eq!("f:1,2,3".parse::<Spec>(), Err(()));
+    }
+
+    #[test]
+    fn messages() {
+        let messages = extract(
+            r#"
+/* TRANSLATORS: greeting */
+printf(_("Hello, "
+         "world\n"));
+/* not for translators */
+puts(N_("Bye"));
+const char *s = ngettext("%d file", "%d files", n);
+puts(pgettext("menu", /* inner */ "Open"));
+puts(gettext(name));
+"#,
+            &Options::default(),
+        );
+
+        assert_eq!(
+            messages,
+            [
+                Message {
+                    context: None,
+                    id: "Hello, world\n".into(),
+                    plural: None,
+                    comments: vec!["TRANSLATORS: greeting".into()],
+                    location: Location {
+                        point: 36,
+                        line: 3,
+                        column: 8
+                    },
+                },
+                Message {
+                    context: None,
+                    id: "Bye".into(),
+                    plural: None,
+                    comments: vec![],
+                    location: Location {
+                        point: 101,
+                        line: 6,
+                        column: 6
+                    },
+                },
+                Message {
+                    context: None,
+                    id: "%d file".into(),
+                    plural: Some("%d files".into()),
+                    comments: vec![],
+                    location: Location {
+                        point: 129,
+                        line: 7,
+                        column: 17
+                    },
+                },
+                Message {
+                    context: Some("menu".into()),
+                    id: "Open".into(),
+                    plural: None,
+                    comments: vec![],
+                    location: Location {
+                        point: 170,
+                        line: 8,
+                        column: 6
+                    },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn catalog() {
+        let options = Options::default();
+        let mut catalog = Catalog::default();
+        catalog.add(
+            "a.c",
+            extract("// TRANSLATORS: quote\n_(\"a\\\"b\");\n", &options),
+        );
+        catalog.add("b.c", extract("_(\"a\\\"b\"); _(\"x\\ny\");", &options));
+
+        assert_eq!(
+            catalog
+                .to_string()
+                .split("\n\n")
+                .skip(1)
+                .collect::<Vec<_>>(),
+            [
+                "#. TRANSLATORS: quote\n#: a.c:2 b.c:1\nmsgid \"a\\\"b\"\nmsgstr \"\"",
+                "#: b.c:1\nmsgid \"\"\n\"x\\n\"\n\"y\"\nmsgstr \"\"\n",
+            ]
+        );
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(
+            po_string("\x07\x08\x0b\x0c\t\r\"\\"),
+            r#""\a\b\v\f\t\r\"\\""#
+        );
+        assert_eq!(po_string("a\0b\x1b\x7f"), r#""a\000b\033\177""#);
+    }
+}
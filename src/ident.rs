@@ -0,0 +1,196 @@
+use unicode_normalization::UnicodeNormalization;
+
+/// Characters allowed in identifiers besides ASCII letters, digits and underscore
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    /// No extended characters (C89)
+    Ascii,
+    /// Ranges of C11 Annex D
+    Annex,
+    /// Unicode `XID_Start` and `XID_Continue` (C23)
+    Xid,
+}
+
+/// C11 Annex D.1: ranges of characters allowed
+const ALLOWED: &[(u32, u32)] = &[
+    (0xA8, 0xA8),
+    (0xAA, 0xAA),
+    (0xAD, 0xAD),
+    (0xAF, 0xAF),
+    (0xB2, 0xB5),
+    (0xB7, 0xBA),
+    (0xBC, 0xBE),
+    (0xC0, 0xD6),
+    (0xD8, 0xF6),
+    (0xF8, 0xFF),
+    (0x100, 0x167F),
+    (0x1681, 0x180D),
+    (0x180F, 0x1FFF),
+    (0x200B, 0x200D),
+    (0x202A, 0x202E),
+    (0x203F, 0x2040),
+    (0x2054, 0x2054),
+    (0x2060, 0x206F),
+    (0x2070, 0x218F),
+    (0x2460, 0x24FF),
+    (0x2776, 0x2793),
+    (0x2C00, 0x2DFF),
+    (0x2E80, 0x2FFF),
+    (0x3004, 0x3007),
+    (0x3021, 0x302F),
+    (0x3031, 0x303F),
+    (0x3040, 0xD7FF),
+    (0xF900, 0xFD3D),
+    (0xFD40, 0xFDCF),
+    (0xFDF0, 0xFE44),
+    (0xFE47, 0xFFFD),
+];
+
+/// C11 Annex D.2: ranges of characters disallowed initially
+const NOT_INITIAL: &[(u32, u32)] = &[
+    (0x300, 0x36F),
+    (0x1DC0, 0x1DFF),
+    (0x20D0, 0x20FF),
+    (0xFE20, 0xFE2F),
+];
+
+fn in_ranges(ranges: &[(u32, u32)], chr: char) -> bool {
+    let code = chr as u32;
+    ranges
+        .binary_search_by(|&(first, last)| {
+            if last < code {
+                core::cmp::Ordering::Less
+            } else if first > code {
+                core::cmp::Ordering::Greater
+            } else {
+                core::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+impl Charset {
+    /// Extended character may appear in identifier
+    fn is_continue(self, chr: char) -> bool {
+        match self {
+            Self::Ascii => false,
+            // planes 1 to 14 except last two code points of each
+            Self::Annex => {
+                in_ranges(ALLOWED, chr)
+                    || (0x10000..0xF0000).contains(&(chr as u32)) && chr as u32 & 0xFFFE != 0xFFFE
+            }
+            Self::Xid => unicode_ident::is_xid_continue(chr),
+        }
+    }
+
+    /// Extended character may start identifier
+    fn is_start(self, chr: char) -> bool {
+        match self {
+            Self::Ascii => false,
+            Self::Annex => self.is_continue(chr) && !in_ranges(NOT_INITIAL, chr),
+            Self::Xid => unicode_ident::is_xid_start(chr),
+        }
+    }
+}
+
+/// Decode character or universal character name at start of text
+///
+/// Returns character, its length in text and whether it is UCN.
+fn next_char(text: &str) -> Option<(char, usize, bool)> {
+    let digits = match text.as_bytes() {
+        [b'\\', b'u', ..] => 4,
+        [b'\\', b'U', ..] => 8,
+        _ => {
+            let chr = text.chars().next()?;
+            return Some((chr, chr.len_utf8(), false));
+        }
+    };
+    let hex = text.get(2..2 + digits)?;
+    if !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    let chr = char::from_u32(u32::from_str_radix(hex, 16).ok()?)?;
+    Some((chr, 2 + digits, true))
+}
+
+/// Length of identifier characters at start of text
+///
+/// When `start` is set the first character should be allowed to start identifier.
+pub fn scan(text: &str, charset: Charset, dollars: bool, start: bool) -> usize {
+    let mut len = 0;
+
+    while let Some((chr, size, ucn)) = next_char(&text[len..]) {
+        let first = start && len == 0;
+        let valid = if ucn && (chr as u32) < 0xA0 {
+            // UCN may not designate basic characters
+            false
+        } else {
+            match chr {
+                'a'..='z' | 'A'..='Z' | '_' => true,
+                '0'..='9' => !first,
+                '$' => dollars,
+                _ if chr.is_ascii() => false,
+                _ if first => charset.is_start(chr),
+                _ => charset.is_continue(chr),
+            }
+        };
+        if !valid {
+            break;
+        }
+        len += size;
+    }
+
+    len
+}
+
+/// Decode universal character names and normalize identifier to NFC
+pub fn extract(slice: &str) -> Option<String> {
+    let mut name = String::with_capacity(slice.len());
+    let mut rest = slice;
+
+    while !rest.is_empty() {
+        let (chr, size, _) = next_char(rest)?;
+        name.push(chr);
+        rest = &rest[size..];
+    }
+
+    Some(name.nfc().collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn charsets() {
+        assert_eq!(scan("café_count+", Charset::Annex, true, true), 11);
+        assert_eq!(scan("café_count+", Charset::Ascii, true, true), 3);
+        // combining acute accent may not start identifier
+        assert_eq!(scan("\u{301}a", Charset::Annex, true, true), 0);
+        assert_eq!(scan("\u{301}a", Charset::Xid, true, true), 0);
+        assert_eq!(scan("a\u{301}", Charset::Xid, true, true), 3);
+        // vertical tilde is punctuation
+        assert_eq!(scan("a\u{2E2F}b", Charset::Xid, true, true), 1);
+        assert_eq!(scan("a\u{2E2F}b", Charset::Annex, true, true), 1);
+        // no-break space is not allowed
+        assert_eq!(scan("a\u{A0}b", Charset::Annex, true, true), 1);
+        assert_eq!(scan("a\u{AA}b", Charset::Annex, true, true), 4);
+        assert_eq!(scan("𝑥1", Charset::Annex, true, true), 5);
+        assert_eq!(scan("$a", Charset::Xid, false, true), 0);
+        assert_eq!(scan("$a", Charset::Ascii, true, true), 2);
+        assert_eq!(scan("1a", Charset::Xid, true, true), 0);
+    }
+
+    #[test]
+    fn ucn() {
+        assert_eq!(scan(r"caf\u00e9 ", Charset::Annex, true, true), 9);
+        assert_eq!(scan(r"a\U0001D465", Charset::Annex, true, true), 11);
+        assert_eq!(scan(r"a\u0041", Charset::Annex, true, true), 1);
+        assert_eq!(scan(r"a\u00e", Charset::Annex, true, true), 1);
+        assert_eq!(scan(r"a\ud800", Charset::Annex, true, true), 1);
+        assert_eq!(scan(r"\u00e9", Charset::Ascii, true, true), 0);
+        assert_eq!(extract(r"caf\u00e9").unwrap(), "caf\u{e9}");
+        assert_eq!(extract("cafe\u{301}").unwrap(), "caf\u{e9}");
+        assert_eq!(extract(r"cafe\u0301").unwrap(), "caf\u{e9}");
+    }
+}
@@ -0,0 +1,274 @@
+use crate::{Dialect, Lexeme, Lexer, Location, Token};
+use core::ops::Range;
+
+/// Lexed token without slice
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    token: Token,
+    span: Range<usize>,
+    location: Location,
+}
+
+/// Lexer keeping lexemes of edited source code
+///
+/// Edits re-lex source from the nearest whitespace before the edit until tokens resynchronize
+/// with previous ones after the edit.
+#[derive(Debug, Clone)]
+pub struct IncrementalLexer {
+    source: String,
+    dialect: Dialect,
+    entries: Vec<Entry>,
+}
+
+impl From<String> for IncrementalLexer {
+    fn from(source: String) -> Self {
+        Self::with_dialect(source, Dialect::default())
+    }
+}
+
+impl From<&str> for IncrementalLexer {
+    fn from(source: &str) -> Self {
+        Self::from(source.to_string())
+    }
+}
+
+impl IncrementalLexer {
+    /// Lex source code of specific dialect
+    pub fn with_dialect(source: String, dialect: Dialect) -> Self {
+        let entries = Lexer::with_dialect(&source, dialect)
+            .map(|lexeme| Entry {
+                token: lexeme.token,
+                span: lexeme.span,
+                location: lexeme.location,
+            })
+            .collect();
+        Self {
+            source,
+            dialect,
+            entries,
+        }
+    }
+
+    /// Source code
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Language dialect
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    /// Number of lexemes
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Source has no lexemes
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Lexeme at index
+    pub fn get(&self, index: usize) -> Option<Lexeme<'_>> {
+        self.entries.get(index).map(|entry| self.lexeme(entry))
+    }
+
+    /// All lexemes
+    pub fn lexemes(&self) -> impl Iterator<Item = Lexeme<'_>> + '_ {
+        self.entries.iter().map(|entry| self.lexeme(entry))
+    }
+
+    fn lexeme(&self, entry: &Entry) -> Lexeme<'_> {
+        Lexeme {
+            token: entry.token,
+            location: entry.location,
+            span: entry.span.clone(),
+            slice: &self.source[entry.span.clone()],
+        }
+    }
+
+    /// Replace byte range of source code with text
+    ///
+    /// Returns range of lexeme indexes which were re-lexed; lexemes outside of it are the same
+    /// as before the edit (with shifted positions).
+    ///
+    /// # Panics
+    ///
+    /// Panics if range is out of bounds or not on `char` boundaries.
+    pub fn edit(&mut self, range: Range<usize>, text: &str) -> Range<usize> {
+        // first lexeme which may be changed: touching the edit or adjacent to such lexeme
+        let mut restart = self
+            .entries
+            .partition_point(|entry| entry.span.end < range.start);
+        while restart > 0
+            && restart < self.entries.len()
+            && self.entries[restart - 1].span.end == self.entries[restart].span.start
+        {
+            restart -= 1;
+        }
+        let start = self
+            .entries
+            .get(restart)
+            .map(|entry| entry.span.start)
+            .unwrap_or(range.start)
+            .min(range.start);
+        let base = match restart.checked_sub(1).map(|index| &self.entries[index]) {
+            Some(entry) => entry
+                .location
+                .advance(&self.source[entry.span.start..start]),
+            None => Location::find(&self.source, start),
+        };
+
+        // first lexeme after the edit
+        let tail = self
+            .entries
+            .partition_point(|entry| entry.span.start < range.end)
+            .max(restart);
+        let edit_end = range.start + text.len();
+        let delta = text.len() as isize - range.len() as isize;
+        let shift = |pos: usize| (pos as isize + delta) as usize;
+
+        self.source.replace_range(range, text);
+
+        let mut replaced = Vec::new();
+        let mut resync = None;
+        let mut old = tail;
+
+        for lexeme in Lexer::with_dialect(&self.source[start..], self.dialect) {
+            let entry = Entry {
+                token: lexeme.token,
+                span: start + lexeme.span.start..start + lexeme.span.end,
+                location: base.join(lexeme.location),
+            };
+
+            if entry.span.start >= edit_end {
+                while old < self.entries.len()
+                    && shift(self.entries[old].span.start) < entry.span.start
+                {
+                    old += 1;
+                }
+                // `/` may be the second half of `//` split when line comments are unsupported
+                let split =
+                    !self.dialect.line_comments() && &self.source[entry.span.clone()] == "/";
+                if let Some(previous) = self.entries.get(old).filter(|_| !split) {
+                    if shift(previous.span.start) == entry.span.start
+                        && previous.span.len() == entry.span.len()
+                        && previous.token == entry.token
+                    {
+                        resync = Some((old, previous.location, entry.location));
+                        break;
+                    }
+                }
+            }
+
+            replaced.push(entry);
+        }
+
+        let count = replaced.len();
+        let end = match resync {
+            Some((old, previous, current)) => {
+                for entry in &mut self.entries[old..] {
+                    entry.span = shift(entry.span.start)..shift(entry.span.end);
+                    if entry.location.line == previous.line {
+                        entry.location.column =
+                            entry.location.column + current.column - previous.column;
+                    }
+                    entry.location.line = entry.location.line + current.line - previous.line;
+                    entry.location.point = entry.span.start;
+                }
+                old
+            }
+            None => self.entries.len(),
+        };
+        self.entries.splice(restart..end, replaced);
+
+        restart..restart + count
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn check(lexer: &IncrementalLexer) {
+        fn entries<'l>(
+            lexemes: impl Iterator<Item = Lexeme<'l>>,
+        ) -> Vec<(Token, Range<usize>, Location, &'l str)> {
+            lexemes
+                .map(|lexeme| (lexeme.token, lexeme.span, lexeme.location, lexeme.slice))
+                .collect()
+        }
+        assert_eq!(
+            entries(lexer.lexemes()),
+            entries(Lexer::with_dialect(lexer.source(), lexer.dialect()))
+        );
+    }
+
+    fn edit(source: &str, range: Range<usize>, text: &str) -> (Vec<String>, IncrementalLexer) {
+        let mut lexer = IncrementalLexer::from(source);
+        let changed = lexer.edit(range, text);
+        check(&lexer);
+        let changed = lexer.lexemes().collect::<Vec<_>>()[changed]
+            .iter()
+            .map(|lexeme| lexeme.slice.to_string())
+            .collect();
+        (changed, lexer)
+    }
+
+    #[test]
+    fn token() {
+        let (changed, _) = edit("int a = b;\nint c;", 8..9, "bc + d");
+        assert_eq!(changed, ["bc", "+", "d"]);
+        let (changed, _) = edit("a . . b", 3..4, "");
+        assert_eq!(changed, ["."]);
+        let (changed, _) = edit("x = a ..;", 7..7, ".");
+        assert_eq!(changed, ["..."]);
+    }
+
+    #[test]
+    fn comments() {
+        let source = "int a;\nint b; /* c */\nint d;\n";
+        let (changed, lexer) = edit(source, 4..4, "/*");
+        assert_eq!(changed, ["/*a;\nint b; /* c */"]);
+        let mut lexer = lexer;
+        let changed = lexer.edit(4..6, "");
+        check(&lexer);
+        assert_eq!(changed, 1..7);
+
+        let (changed, _) = edit(source, 18..18, "*/ x /*");
+        assert_eq!(changed, ["/* c*/", "x", "/* */"]);
+    }
+
+    #[test]
+    fn strings() {
+        let (changed, _) = edit("s = \"a b\"; t;", 6..7, "\"+\"");
+        assert_eq!(changed, ["\"a\"", "+", "\"b\""]);
+        let (_, mut lexer) = edit("s = \"a\"; t = 1;", 4..4, "\"");
+        lexer.edit(4..5, "");
+        check(&lexer);
+    }
+
+    #[test]
+    fn random() {
+        let source = "#include <a.h>\n/* block\n * comment */\nint f(int x) {\n    return x + 0x1f; // c\n}\nchar *s = \"str\\\ning\";\n";
+        let inserts = [
+            "/*", "*/", "\"", "x", " ", "\n", "//", "1.", "e+", "\\\n", "'",
+        ];
+        for dialect in ["gnu17", "c89"] {
+            let mut lexer = IncrementalLexer::with_dialect(source.into(), dialect.parse().unwrap());
+            let mut seed = 7usize;
+            for _ in 0..2000 {
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                let len = lexer.source().len();
+                let start = (seed >> 33) % (len + 1);
+                let end = (start + (seed >> 20) % 3).min(len);
+                let text = inserts[(seed >> 40) % inserts.len()];
+                lexer.edit(start..end, text);
+                check(&lexer);
+            }
+        }
+    }
+}
@@ -1,5 +1,8 @@
+use crate::Standard;
+
 /// Keyword token
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum Keyword {
     /// auto
@@ -94,6 +97,78 @@ pub enum Keyword {
     FuncName,
 }
 
+impl Keyword {
+    /// Keyword spelling
+    pub fn as_str(self) -> &'static str {
+        use Keyword::*;
+        match self {
+            Auto => "auto",
+            Break => "break",
+            Case => "case",
+            Char => "char",
+            Const => "const",
+            Continue => "continue",
+            Default => "default",
+            Do => "do",
+            Double => "double",
+            Else => "else",
+            Enum => "enum",
+            Extern => "extern",
+            Float => "float",
+            For => "for",
+            Goto => "goto",
+            If => "if",
+            Inline => "inline",
+            Int => "int",
+            Long => "long",
+            Register => "register",
+            Restrict => "restrict",
+            Return => "return",
+            Short => "short",
+            Signed => "signed",
+            SizeOf => "sizeof",
+            Static => "static",
+            Struct => "struct",
+            Switch => "switch",
+            TypeDef => "typedef",
+            Union => "union",
+            Unsigned => "unsigned",
+            Void => "void",
+            Volatile => "volatile",
+            While => "while",
+            AlignAs => "_Alignas",
+            AlignOf => "_Alignof",
+            Atomic => "_Atomic",
+            Bool => "_Bool",
+            Complex => "_Complex",
+            Generic => "_Generic",
+            Imaginary => "_Imaginary",
+            NoReturn => "_Noreturn",
+            StaticAssert => "_Static_assert",
+            ThreadLocal => "_Thread_local",
+            FuncName => "__func__",
+        }
+    }
+
+    /// Standard which introduces keyword
+    pub fn standard(self) -> Standard {
+        use Keyword::*;
+        match self {
+            Inline | Restrict | Bool | Complex | Imaginary | FuncName => Standard::C99,
+            AlignAs | AlignOf | Atomic | Generic | NoReturn | StaticAssert | ThreadLocal => {
+                Standard::C11
+            }
+            _ => Standard::C89,
+        }
+    }
+}
+
+impl core::fmt::Display for Keyword {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 impl std::str::FromStr for Keyword {
     type Err = ();
 
@@ -162,6 +237,15 @@ mod test {
         assert_eq!("__func__".parse::<Keyword>(), Ok(Keyword::FuncName));
     }
 
+    #[test]
+    fn spelling() {
+        assert_eq!(Keyword::StaticAssert.to_string(), "_Static_assert");
+        assert_eq!(Keyword::TypeDef.as_str(), "typedef");
+        for (name, keyword) in KEYWORDS.entries() {
+            assert_eq!(keyword.as_str(), *name);
+        }
+    }
+
     #[test]
     fn not_a_keyword() {
         assert_eq!("123".parse::<Keyword>(), Err(()));
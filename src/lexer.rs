@@ -1,10 +1,102 @@
-#[derive(Clone, Copy, Default)]
+use crate::{
+    ident::{self, Charset},
+    Diagnostic, DiagnosticKind,
+};
+
+#[derive(Clone)]
 pub struct State {
     pub is_directive: bool,
     pub is_include: bool,
+    /// Line comments unsupported by dialect
+    pub no_line_comments: bool,
+    /// Extended characters allowed in identifiers
+    pub identifiers: Charset,
+    /// Dollar sign allowed in identifiers
+    pub dollars: bool,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            is_directive: false,
+            is_include: false,
+            no_line_comments: false,
+            identifiers: Charset::Annex,
+            dollars: true,
+            diagnostics: Vec::new(),
+        }
+    }
+}
+
+/// Check identifier start and continue it with extended characters and UCNs
+fn identifier(lex: &mut logos::Lexer<Token>) -> bool {
+    let (charset, dollars) = (lex.extras.identifiers, lex.extras.dollars);
+    let slice = lex.slice();
+
+    if ident::scan(slice, charset, dollars, true) < slice.len() {
+        return false;
+    }
+
+    let len = ident::scan(lex.remainder(), charset, dollars, false);
+    lex.bump(len);
+    true
+}
+
+fn block_comment(lex: &mut logos::Lexer<Token>) {
+    let start = lex.span().end;
+    let remainder = lex.remainder();
+    let (body, len) = if let Some(end) = remainder.find("*/") {
+        (&remainder[..end], end + 2)
+    } else {
+        lex.extras.diagnostics.push(Diagnostic {
+            kind: DiagnosticKind::UnterminatedComment,
+            span: lex.span().start..start + remainder.len(),
+        });
+        (remainder, remainder.len())
+    };
+
+    for (pos, _) in body.match_indices("/*") {
+        lex.extras.diagnostics.push(Diagnostic {
+            kind: DiagnosticKind::NestedComment,
+            span: start + pos..start + pos + 2,
+        });
+    }
+
+    lex.bump(len);
+}
+
+fn line_comment(lex: &mut logos::Lexer<Token>) {
+    if lex.extras.no_line_comments {
+        return;
+    }
+
+    let start = lex.span().end;
+    let remainder = lex.remainder();
+    let mut len = 0;
+
+    loop {
+        let line = &remainder[len..];
+        let end = line.find('\n').unwrap_or(line.len());
+        let text = line[..end].strip_suffix('\r').unwrap_or(&line[..end]);
+
+        if end < line.len() && text.ends_with('\\') {
+            lex.extras.diagnostics.push(Diagnostic {
+                kind: DiagnosticKind::SplicedLineComment,
+                span: start + len + text.len() - 1..start + len + text.len(),
+            });
+            len += end + 1;
+        } else {
+            len += text.len();
+            break;
+        }
+    }
+
+    lex.bump(len);
 }
 
-#[derive(Debug, Clone, Copy, logos::Logos, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, logos::Logos, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[logos(extras = State)]
 // octal digit
 #[logos(subpattern o = "[0-7]")]
@@ -27,8 +119,8 @@ pub struct State {
 #[logos(subpattern fs = "[fFlL]")]
 // integer suffix
 #[logos(subpattern is = "([uU]([lL]|ll|LL)?)|(([lL]|ll|LL)[uU]?)")]
-#[logos(subpattern l = "[a-zA-Z_$]")]
-#[logos(subpattern a = "[a-zA-Z_$0-9]")]
+#[logos(subpattern l = "[a-zA-Z_]")]
+#[logos(subpattern a = "[a-zA-Z_0-9]")]
 // char prefix
 #[logos(subpattern cp = r"[uUL]")]
 // string prefix
@@ -38,11 +130,8 @@ pub struct State {
 // escape sequence
 #[logos(subpattern es = r#"[\\](['"%?\\abefnrtv]|[0-7]+|[xu][a-fA-F0-9]+|[\r]?[\n])"#)]
 pub enum Token {
-    #[regex("//[^\r\n]*")]
-    #[token("/*", |lex| {
-        lex.bump(lex.remainder().find("*/")? + 2);
-        Some(())
-    })]
+    #[token("//", line_comment)]
+    #[token("/*", block_comment)]
     Comment,
 
     #[regex(r"\.\.\.")]
@@ -69,7 +158,13 @@ pub enum Token {
     )]
     Float,
 
-    #[regex("(?&l)(?&a)*")]
+    #[regex("(?&l)(?&a)*", identifier)]
+    // extended characters, dollar sign and universal character names are checked by dialect
+    #[regex("[^\\x00-\\x7f]|[$]", identifier)]
+    #[regex(
+        r"\\u(?&h)(?&h)(?&h)(?&h)|\\U(?&h)(?&h)(?&h)(?&h)(?&h)(?&h)(?&h)(?&h)",
+        identifier
+    )]
     Identifier,
 
     #[error]
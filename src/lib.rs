@@ -1,27 +1,58 @@
 #![doc = include_str!("../README.md")]
 
+mod buffer;
+mod bytes;
 mod char;
-mod comment;
+pub mod comment;
+pub mod compdb;
+mod cursor;
+pub mod decl;
+mod diagnostic;
+mod dialect;
+mod directive;
+pub mod doc;
+pub mod encoding;
 mod float;
+pub mod grep;
+pub mod highlight;
+pub mod i18n;
+mod ident;
+mod incremental;
 mod int;
 mod keyword;
 mod lexer;
+pub mod license;
+pub mod markers;
+pub mod semantic;
+pub mod stats;
+mod stream;
 mod string;
+pub mod tags;
+pub mod tree;
 
+pub use buffer::{MemoryUsage, TokenBuffer, TokenFlags, TokenIter, TokenSlice};
+pub use bytes::{ByteLexeme, ByteLexer, ByteSource};
+pub use cursor::{Checkpoint, Cursor, Delimiter};
+pub use diagnostic::{Diagnostic, DiagnosticKind, Severity};
+pub use dialect::{Dialect, Standard};
 pub use float::Float;
+pub use incremental::IncrementalLexer;
 pub use int::Int;
 pub use keyword::Keyword;
 pub use lexer::Token;
+pub use stream::{ChunkReader, OwnedLexeme, StreamLexer};
 
 /// C lexeme
+///
+/// Lexemes borrowing source are serialized only, deserialize them as `OwnedLexeme`.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Lexeme<'l> {
     /// Token kind
     pub token: Token,
-    /* TODO:
     /// Position in source code
     pub location: Location,
-    */
+    /// Byte range in source code
     pub span: core::ops::Range<usize>,
     /// String slice
     pub slice: &'l str,
@@ -51,6 +82,21 @@ impl<'l> Lexeme<'l> {
         }
     }
 
+    /// Extract keyword reserved in dialect
+    pub fn keyword_in(&self, dialect: Dialect) -> Option<Keyword> {
+        self.keyword()
+            .filter(|keyword| dialect.is_keyword(*keyword))
+    }
+
+    /// Extract identifier name with decoded universal character names normalized to NFC
+    pub fn identifier(&self) -> Option<String> {
+        if self.token == Token::Identifier {
+            ident::extract(self.slice)
+        } else {
+            None
+        }
+    }
+
     /// Extract text from comment
     pub fn comment(&self) -> Option<String> {
         if self.token == Token::Comment {
@@ -60,6 +106,25 @@ impl<'l> Lexeme<'l> {
         }
     }
 
+    /// Extract text from comment using options
+    pub fn comment_with(&self, options: &comment::Options) -> Option<comment::Extracted> {
+        if self.token == Token::Comment {
+            comment::extract_with(self.slice, options)
+                .map(|extracted| extracted.shift(self.span.start))
+        } else {
+            None
+        }
+    }
+
+    /// Extract documentation comment
+    pub fn doc(&self) -> Option<doc::Comment> {
+        if self.token == Token::Comment {
+            doc::Comment::parse(self.slice)
+        } else {
+            None
+        }
+    }
+
     /// Extract text from character literal
     pub fn char(&self) -> Option<char> {
         if self.token == Token::Char {
@@ -97,30 +162,119 @@ impl<'l> Lexeme<'l> {
     }
 }
 
-/* TODO:
 /// C token location
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Location {
-    /// Source code position
-    pub point: u32,
-    /// Source code line
+    /// Source code position in bytes
+    pub point: usize,
+    /// Source code line (starting from 1)
     pub line: u32,
-    /// Source code column
+    /// Source code column in bytes (starting from 1)
     pub column: u32,
 }
-*/
+
+impl Default for Location {
+    fn default() -> Self {
+        Self {
+            point: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+}
+
+impl core::fmt::Display for Location {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+impl Location {
+    /// Find location of position in source code
+    pub fn find(source: &str, point: usize) -> Self {
+        Self::default().advance(&source[..point])
+    }
+
+    /// Location after text which starts at this location
+    pub fn advance(&self, text: &str) -> Self {
+        let point = self.point + text.len();
+        if let Some(pos) = text.rfind('\n') {
+            Self {
+                point,
+                line: self.line + text.matches('\n').count() as u32,
+                column: (text.len() - pos) as u32,
+            }
+        } else {
+            Self {
+                point,
+                line: self.line,
+                column: self.column + text.len() as u32,
+            }
+        }
+    }
+
+    /// Convert location relative to this one into absolute
+    pub(crate) fn join(&self, relative: Location) -> Self {
+        Self {
+            point: self.point + relative.point,
+            line: self.line + relative.line - 1,
+            column: if relative.line == 1 {
+                self.column + relative.column - 1
+            } else {
+                relative.column
+            },
+        }
+    }
+}
 
 /// C Lexer
 pub struct Lexer<'l> {
     inner: logos::Lexer<'l, Token>,
+    /// Location of end of last lexeme
+    location: Location,
+    dialect: Dialect,
 }
 
 impl<'l> From<&'l str> for Lexer<'l> {
     fn from(s: &'l str) -> Self {
+        Self::with_dialect(s, Dialect::default())
+    }
+}
+
+impl<'l> Lexer<'l> {
+    /// Create lexer for specific dialect
+    pub fn with_dialect(s: &'l str, dialect: Dialect) -> Self {
+        let mut inner = logos::Lexer::<Token>::new(s);
+        inner.extras.no_line_comments = !dialect.line_comments();
+        inner.extras.identifiers = dialect.identifier_charset();
+        inner.extras.dollars = dialect.dollars;
         Self {
-            inner: logos::Lexer::new(s),
+            inner,
+            location: Location::default(),
+            dialect,
         }
     }
+
+    /// Source code
+    pub fn source(&self) -> &'l str {
+        self.inner.source()
+    }
+
+    /// Language dialect
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    /// Diagnostics reported so far
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.inner.extras.diagnostics
+    }
+
+    /// Take diagnostics reported so far
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        core::mem::take(&mut self.inner.extras.diagnostics)
+    }
 }
 
 impl<'l> Iterator for Lexer<'l> {
@@ -130,16 +284,243 @@ impl<'l> Iterator for Lexer<'l> {
         self.inner.next().map(|token| {
             let span = self.inner.span();
             let slice = self.inner.slice();
-            Lexeme { token, span, slice }
+            let source = self.inner.source();
+            let location = self
+                .location
+                .advance(&source[self.location.point..span.start]);
+
+            if token == Token::Comment && slice == "//" && self.inner.extras.no_line_comments {
+                // lex again after first slash (e.g. `//*` is division followed by comment)
+                let extras = core::mem::take(&mut self.inner.extras);
+                self.inner = logos::Lexer::with_extras(source, extras);
+                self.inner.bump(span.start + 1);
+                self.location = location.advance("/");
+                return Lexeme {
+                    token: Token::Symbol,
+                    location,
+                    span: span.start..span.start + 1,
+                    slice: &slice[..1],
+                };
+            }
+
+            self.location = location.advance(slice);
+
+            Lexeme {
+                token,
+                location,
+                span,
+                slice,
+            }
         })
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    fn lex(src: &str) -> (Vec<(Token, &str)>, Vec<Diagnostic>) {
+        let mut lexer = Lexer::from(src);
+        let lexemes = lexer
+            .by_ref()
+            .map(|lexeme| (lexeme.token, lexeme.slice))
+            .collect();
+        (lexemes, lexer.take_diagnostics())
+    }
+
     #[test]
     fn it_works() {
         let result = 2 + 2;
         assert_eq!(result, 4);
     }
+
+    #[test]
+    fn location() {
+        let src = "int a;\n/* b\n */ c";
+        let locations = Lexer::from(src)
+            .map(|lexeme| (lexeme.location.line, lexeme.location.column))
+            .collect::<Vec<_>>();
+        assert_eq!(locations, [(1, 1), (1, 5), (1, 6), (2, 1), (3, 5)]);
+        assert_eq!(Location::find(src, 16).to_string(), "3:5");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_lexeme() {
+        let src = "int a;";
+        let lexemes = Lexer::from(src).collect::<Vec<_>>();
+        let json = serde_json::to_string(&lexemes[0]).unwrap();
+        assert_eq!(
+            json,
+            r#"{"token":"Identifier","location":{"point":0,"line":1,"column":1},"span":{"start":0,"end":3},"slice":"int"}"#
+        );
+        let lexeme: OwnedLexeme = serde_json::from_str(&json).unwrap();
+        assert_eq!(lexeme.slice, "int");
+        assert_eq!(lexeme.as_lexeme().keyword(), Some(Keyword::Int));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_escaped() {
+        let src = r#"char *s = "a\n"; /* "q" \ */"#;
+        for lexeme in Lexer::from(src) {
+            let json = serde_json::to_string(&lexeme).unwrap();
+            let owned: OwnedLexeme = serde_json::from_str(&json).unwrap();
+            assert_eq!(owned.slice, lexeme.slice);
+            assert_eq!(owned.span, lexeme.span);
+        }
+    }
+
+    #[test]
+    fn dialect() {
+        let src = "a //b\nc";
+        let lexemes = |dialect: &str| {
+            Lexer::with_dialect(src, dialect.parse().unwrap())
+                .map(|lexeme| (lexeme.token, lexeme.slice, lexeme.location.column))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            lexemes("c89"),
+            [
+                (Token::Identifier, "a", 1),
+                (Token::Symbol, "/", 3),
+                (Token::Symbol, "/", 4),
+                (Token::Identifier, "b", 5),
+                (Token::Identifier, "c", 1),
+            ]
+        );
+        assert_eq!(
+            lexemes("gnu89"),
+            [
+                (Token::Identifier, "a", 1),
+                (Token::Comment, "//b", 3),
+                (Token::Identifier, "c", 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn c89_slashes() {
+        let lexemes = |src| {
+            Lexer::with_dialect(src, "c89".parse().unwrap())
+                .map(|lexeme| (lexeme.token, lexeme.slice, lexeme.location.column))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            lexemes("b //* c */ d"),
+            [
+                (Token::Identifier, "b", 1),
+                (Token::Symbol, "/", 3),
+                (Token::Comment, "/* c */", 4),
+                (Token::Identifier, "d", 12),
+            ]
+        );
+        assert_eq!(
+            lexemes("x //= 2"),
+            [
+                (Token::Identifier, "x", 1),
+                (Token::Symbol, "/", 3),
+                (Token::Symbol, "/=", 4),
+                (Token::Int, "2", 7),
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_comment() {
+        let src = "/* a /* b */ c";
+        assert_eq!(
+            lex(src),
+            (
+                vec![(Token::Comment, "/* a /* b */"), (Token::Identifier, "c")],
+                vec![Diagnostic {
+                    kind: DiagnosticKind::NestedComment,
+                    span: 5..7
+                }]
+            )
+        );
+    }
+
+    #[test]
+    fn spliced_line_comment() {
+        let src = "// a \\\nb\nc";
+        assert_eq!(
+            lex(src),
+            (
+                vec![(Token::Comment, "// a \\\nb"), (Token::Identifier, "c")],
+                vec![Diagnostic {
+                    kind: DiagnosticKind::SplicedLineComment,
+                    span: 5..6
+                }]
+            )
+        );
+    }
+
+    #[test]
+    fn unterminated_comment() {
+        let src = "a /* b\nc;";
+        let (lexemes, diagnostics) = lex(src);
+        assert_eq!(
+            lexemes,
+            [(Token::Identifier, "a"), (Token::Comment, "/* b\nc;")]
+        );
+        assert_eq!(
+            diagnostics,
+            [Diagnostic {
+                kind: DiagnosticKind::UnterminatedComment,
+                span: 2..9
+            }]
+        );
+        assert_eq!(diagnostics[0].severity(), Severity::Error);
+    }
+
+    #[test]
+    fn unicode_identifiers() {
+        let src = "café_count = x² + cafe\u{301} + caf\\u00e9 + $a + a·b;";
+        let lexemes = |dialect: Dialect| {
+            Lexer::with_dialect(src, dialect)
+                .filter(|lexeme| lexeme.token != Token::Symbol)
+                .map(|lexeme| (lexeme.token, lexeme.slice))
+                .collect::<Vec<_>>()
+        };
+        let c17 = Dialect::default();
+        assert_eq!(
+            lexemes(c17),
+            [
+                (Token::Identifier, "café_count"),
+                (Token::Identifier, "x²"),
+                (Token::Identifier, "cafe\u{301}"),
+                (Token::Identifier, r"caf\u00e9"),
+                (Token::Identifier, "$a"),
+                (Token::Identifier, "a·b"),
+            ]
+        );
+        // superscript digit is not XID_Continue
+        let c23 = "c23".parse::<Dialect>().unwrap();
+        assert_eq!(
+            lexemes(c23)[1..3],
+            [(Token::Identifier, "x"), (Token::Unknown, "²")]
+        );
+        let c89 = Dialect {
+            dollars: false,
+            .."c89".parse().unwrap()
+        };
+        assert_eq!(
+            lexemes(c89)[..6],
+            [
+                (Token::Identifier, "caf"),
+                (Token::Unknown, "é"),
+                (Token::Identifier, "_count"),
+                (Token::Identifier, "x"),
+                (Token::Unknown, "²"),
+                (Token::Identifier, "cafe"),
+            ]
+        );
+
+        let names = Lexer::from(src)
+            .filter_map(|lexeme| lexeme.identifier())
+            .collect::<Vec<_>>();
+        assert_eq!(names[2..4], ["café", "café"]);
+        assert_eq!(Lexer::from("1").next().unwrap().identifier(), None);
+    }
 }
@@ -0,0 +1,581 @@
+//! License and copyright headers
+//!
+//! Finds `SPDX-License-Identifier` expressions and copyright notices in file header comments.
+
+use crate::{
+    comment::{self, Options},
+    markers::{self, Tag},
+    Lexer, Location, Token,
+};
+use core::ops::{Range, RangeInclusive};
+
+/// SPDX license expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Expr {
+    /// License identifier like `MIT` or `GPL-2.0+`
+    License {
+        /// License identifier
+        id: String,
+        /// Or any later version (`+`)
+        plus: bool,
+    },
+    /// License with exception (`WITH`)
+    With {
+        /// License
+        license: Box<Expr>,
+        /// Exception identifier
+        exception: String,
+    },
+    /// Conjunctive licenses (`AND`)
+    And(Box<Expr>, Box<Expr>),
+    /// Disjunctive licenses (`OR`)
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// License identifiers used in expression
+    pub fn licenses(&self) -> Vec<&str> {
+        let mut ids = Vec::new();
+        self.collect_licenses(&mut ids);
+        ids
+    }
+
+    fn collect_licenses<'a>(&'a self, ids: &mut Vec<&'a str>) {
+        match self {
+            Self::License { id, .. } => ids.push(id),
+            Self::With { license, .. } => license.collect_licenses(ids),
+            Self::And(left, right) | Self::Or(left, right) => {
+                left.collect_licenses(ids);
+                right.collect_licenses(ids);
+            }
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::Or(..) => 0,
+            Self::And(..) => 1,
+            _ => 2,
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut core::fmt::Formatter, precedence: u8) -> core::fmt::Result {
+        if self.precedence() < precedence {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+impl core::fmt::Display for Expr {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::License { id, plus } => {
+                f.write_str(id)?;
+                if *plus {
+                    f.write_str("+")?;
+                }
+                Ok(())
+            }
+            Self::With { license, exception } => {
+                license.fmt_operand(f, 2)?;
+                write!(f, " WITH {}", exception)
+            }
+            Self::And(left, right) => {
+                left.fmt_operand(f, 1)?;
+                f.write_str(" AND ")?;
+                right.fmt_operand(f, 2)
+            }
+            Self::Or(left, right) => {
+                left.fmt_operand(f, 0)?;
+                f.write_str(" OR ")?;
+                right.fmt_operand(f, 1)
+            }
+        }
+    }
+}
+
+impl core::str::FromStr for Expr {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(s)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        if parser.pos < parser.tokens.len() {
+            return Err(());
+        }
+        Ok(expr)
+    }
+}
+
+fn tokenize(s: &str) -> Result<Vec<&str>, ()> {
+    let mut tokens = Vec::new();
+    let mut rest = s.trim();
+
+    while !rest.is_empty() {
+        let len = if rest.starts_with(['(', ')']) {
+            1
+        } else {
+            let len = rest
+                .find(|chr: char| chr.is_whitespace() || chr == '(' || chr == ')')
+                .unwrap_or(rest.len());
+            if !rest[..len]
+                .chars()
+                .all(|chr| chr.is_ascii_alphanumeric() || matches!(chr, '.' | '-' | '+' | ':'))
+            {
+                return Err(());
+            }
+            len
+        };
+        tokens.push(&rest[..len]);
+        rest = rest[len..].trim_start();
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<&'a str>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a str> {
+        self.tokens.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, operator: &str) -> bool {
+        if self
+            .peek()
+            .map(|token| token.eq_ignore_ascii_case(operator))
+            .unwrap_or(false)
+        {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn id(&mut self) -> Result<&'a str, ()> {
+        let token = self.peek().ok_or(())?;
+        if token == "(" || token == ")" || is_operator(token) {
+            return Err(());
+        }
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn or(&mut self) -> Result<Expr, ()> {
+        let mut expr = self.and()?;
+        while self.eat("OR") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, ()> {
+        let mut expr = self.with()?;
+        while self.eat("AND") {
+            expr = Expr::And(Box::new(expr), Box::new(self.with()?));
+        }
+        Ok(expr)
+    }
+
+    fn with(&mut self) -> Result<Expr, ()> {
+        let expr = self.atom()?;
+        if self.eat("WITH") {
+            Ok(Expr::With {
+                license: Box::new(expr),
+                exception: self.id()?.into(),
+            })
+        } else {
+            Ok(expr)
+        }
+    }
+
+    fn atom(&mut self) -> Result<Expr, ()> {
+        if self.eat("(") {
+            let expr = self.or()?;
+            if !self.eat(")") {
+                return Err(());
+            }
+            Ok(expr)
+        } else {
+            let id = self.id()?;
+            let (id, plus) = if let Some(id) = id.strip_suffix('+') {
+                (id, true)
+            } else {
+                (id, false)
+            };
+            if id.is_empty() || id.contains('+') {
+                return Err(());
+            }
+            Ok(Expr::License {
+                id: id.into(),
+                plus,
+            })
+        }
+    }
+}
+
+fn is_operator(token: &str) -> bool {
+    ["AND", "OR", "WITH"]
+        .iter()
+        .any(|operator| token.eq_ignore_ascii_case(operator))
+}
+
+/// License identifier found in comments
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct License {
+    /// Expression text
+    pub text: String,
+    /// Parsed expression
+    pub expr: Option<Expr>,
+    /// Location of identifier tag
+    pub location: Location,
+}
+
+/// Copyright notice
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Copyright {
+    /// Copyright years
+    pub years: Vec<RangeInclusive<u16>>,
+    /// Copyright holder
+    pub holder: String,
+    /// Notice location
+    pub location: Location,
+}
+
+/// License information issue
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Issue {
+    /// No license identifier found
+    NoLicense,
+    /// License expression cannot be parsed
+    InvalidExpression(String),
+    /// Different license expressions found
+    Conflict(Vec<String>),
+}
+
+impl core::fmt::Display for Issue {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Self::NoLicense => f.write_str("no license identifier"),
+            Self::InvalidExpression(text) => write!(f, "invalid license expression: {}", text),
+            Self::Conflict(texts) => write!(f, "conflicting licenses: {}", texts.join("; ")),
+        }
+    }
+}
+
+/// License information of source file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Info {
+    /// Span of header comments
+    pub header: Option<Range<usize>>,
+    /// License identifiers
+    pub licenses: Vec<License>,
+    /// Copyright notices in header
+    pub copyrights: Vec<Copyright>,
+    /// Found issues
+    pub issues: Vec<Issue>,
+}
+
+impl Info {
+    /// License expression when it is unambiguous
+    pub fn license(&self) -> Option<&Expr> {
+        if self
+            .issues
+            .iter()
+            .any(|issue| matches!(issue, Issue::Conflict(_)))
+        {
+            None
+        } else {
+            self.licenses
+                .iter()
+                .find_map(|license| license.expr.as_ref())
+        }
+    }
+}
+
+/// Scan source code for license information
+pub fn scan(source: &str) -> Info {
+    let mut info = Info::default();
+    let tag = markers::Options {
+        tags: vec!["SPDX-License-Identifier".parse::<Tag>().unwrap()],
+    };
+    let mut in_header = true;
+    let mut markers = Vec::new();
+
+    for lexeme in Lexer::from(source) {
+        if lexeme.token != Token::Comment {
+            in_header = false;
+            continue;
+        }
+
+        markers::scan_lexeme(&lexeme, &tag, &mut markers);
+
+        if in_header {
+            info.header = Some(match info.header {
+                Some(header) => header.start..lexeme.span.end,
+                None => lexeme.span.clone(),
+            });
+            if let Some(text) = lexeme.comment_with(&Options::clean()) {
+                scan_copyrights(&text, source, &mut info.copyrights);
+            }
+        }
+    }
+
+    for marker in markers {
+        let expr = marker.message.parse().ok();
+        if expr.is_none() {
+            info.issues
+                .push(Issue::InvalidExpression(marker.message.clone()));
+        }
+        info.licenses.push(License {
+            text: marker.message,
+            expr,
+            location: marker.location,
+        });
+    }
+
+    let mut texts = info
+        .licenses
+        .iter()
+        .map(|license| {
+            license
+                .expr
+                .as_ref()
+                .map(|expr| expr.to_string())
+                .unwrap_or_else(|| license.text.clone())
+        })
+        .collect::<Vec<_>>();
+    texts.sort();
+    texts.dedup();
+
+    match texts.len() {
+        0 => info.issues.push(Issue::NoLicense),
+        1 => {}
+        _ => info.issues.push(Issue::Conflict(texts)),
+    }
+
+    info
+}
+
+fn scan_copyrights(text: &comment::Extracted, source: &str, copyrights: &mut Vec<Copyright>) {
+    let mut offset = 0;
+    for line in text.text.split('\n') {
+        if let Some(pos) = find_copyright(line) {
+            if let Some((years, holder)) = parse_copyright(&line[pos..]) {
+                copyrights.push(Copyright {
+                    years,
+                    holder,
+                    location: Location::find(source, text.source_offset(offset + pos)),
+                });
+            }
+        }
+        offset += line.len() + 1;
+    }
+}
+
+fn find_copyright(line: &str) -> Option<usize> {
+    line.find("Copyright")
+        .or_else(|| line.find("COPYRIGHT"))
+        .or_else(|| line.find('©'))
+}
+
+/// Parse copyright notice like `Copyright (C) 2010-2012, 2015 Holder`
+fn parse_copyright(text: &str) -> Option<(Vec<RangeInclusive<u16>>, String)> {
+    let mut rest = text
+        .trim_start_matches("Copyright")
+        .trim_start_matches("COPYRIGHT")
+        .trim_start();
+
+    loop {
+        let next = rest
+            .trim_start_matches(['©', ':'])
+            .trim_start_matches("(C)")
+            .trim_start_matches("(c)")
+            .trim_start();
+        if next.len() == rest.len() {
+            break;
+        }
+        rest = next;
+    }
+
+    let mut years = Vec::new();
+
+    loop {
+        let len = rest
+            .find(|chr: char| !chr.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if len != 4 {
+            break;
+        }
+        let start = rest[..len].parse::<u16>().ok()?;
+        rest = &rest[len..];
+
+        let end = if let Some(tail) = rest.strip_prefix(['-', '–']) {
+            let tail = tail.trim_start();
+            let len = tail
+                .find(|chr: char| !chr.is_ascii_digit())
+                .unwrap_or(tail.len());
+            let end = match len {
+                4 => tail[..len].parse::<u16>().ok()?,
+                // short form like `2010-12` (or `1998-02` crossing century)
+                2 => {
+                    let end = start / 100 * 100 + tail[..len].parse::<u16>().ok()?;
+                    if end < start {
+                        end + 100
+                    } else {
+                        end
+                    }
+                }
+                _ => break,
+            };
+            rest = &tail[len..];
+            end
+        } else {
+            start
+        };
+
+        years.push(start..=end);
+        rest = rest.trim_start().trim_start_matches(',').trim_start();
+    }
+
+    let holder = rest
+        .trim()
+        .trim_end_matches("All rights reserved.")
+        .trim_end_matches("All Rights Reserved.")
+        .trim()
+        .trim_start_matches("by ")
+        .trim_end_matches(['.', ','])
+        .trim();
+
+    if years.is_empty() && holder.is_empty() {
+        None
+    } else {
+        Some((years, holder.into()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn license(id: &str, plus: bool) -> Expr {
+        Expr::License {
+            id: id.into(),
+            plus,
+        }
+    }
+
+    #[test]
+    fn expr_simple() {
+        assert_eq!("MIT".parse(), Ok(license("MIT", false)));
+        assert_eq!("GPL-2.0+".parse(), Ok(license("GPL-2.0", true)));
+    }
+
+    #[test]
+    fn expr_precedence() {
+        let expr = "MIT OR Apache-2.0 AND GPL-2.0-only WITH Linux-syscall-note"
+            .parse::<Expr>()
+            .unwrap();
+        assert_eq!(
+            expr,
+            Expr::Or(
+                Box::new(license("MIT", false)),
+                Box::new(Expr::And(
+                    Box::new(license("Apache-2.0", false)),
+                    Box::new(Expr::With {
+                        license: Box::new(license("GPL-2.0-only", false)),
+                        exception: "Linux-syscall-note".into(),
+                    })
+                ))
+            )
+        );
+        assert_eq!(
+            expr.to_string(),
+            "MIT OR Apache-2.0 AND GPL-2.0-only WITH Linux-syscall-note"
+        );
+        assert_eq!(expr.licenses(), ["MIT", "Apache-2.0", "GPL-2.0-only"]);
+    }
+
+    #[test]
+    fn expr_parens() {
+        let expr = "(MIT OR BSD-3-Clause) and LicenseRef-Foo"
+            .parse::<Expr>()
+            .unwrap();
+        assert_eq!(expr.to_string(), "(MIT OR BSD-3-Clause) AND LicenseRef-Foo");
+    }
+
+    #[test]
+    fn expr_invalid() {
+        assert!("".parse::<Expr>().is_err());
+        assert!("MIT OR".parse::<Expr>().is_err());
+        assert!("(MIT".parse::<Expr>().is_err());
+        assert!("MIT Apache-2.0".parse::<Expr>().is_err());
+        assert!("MIT/X11".parse::<Expr>().is_err());
+    }
+
+    #[test]
+    fn copyright() {
+        assert_eq!(
+            parse_copyright("Copyright (C) 2010-12, 2015 John Doe <jd@example.com>"),
+            Some((
+                vec![2010..=2012, 2015..=2015],
+                "John Doe <jd@example.com>".into()
+            ))
+        );
+        assert_eq!(
+            parse_copyright("Copyright © 2020 ACME Corp. All rights reserved."),
+            Some((vec![2020..=2020], "ACME Corp".into()))
+        );
+        assert_eq!(
+            parse_copyright("Copyright 1998-02 Jane Roe"),
+            Some((vec![1998..=2002], "Jane Roe".into()))
+        );
+        assert_eq!(parse_copyright("Copyright"), None);
+    }
+
+    #[test]
+    fn header() {
+        let src = r#"// SPDX-License-Identifier: GPL-2.0-only
+/*
+ * Copyright (C) 2019-2021 Jane Roe
+ */
+
+#include <stdio.h>
+
+/* Copyright 2000 Not In Header */
+"#;
+        let info = scan(src);
+        assert_eq!(info.header, Some(0..src.find("\n\n").unwrap()));
+        assert_eq!(info.license(), Some(&license("GPL-2.0-only", false)));
+        assert_eq!(info.issues, []);
+        assert_eq!(info.copyrights.len(), 1);
+        assert_eq!(info.copyrights[0].holder, "Jane Roe");
+        assert_eq!(info.copyrights[0].years, [2019..=2021]);
+        assert_eq!(info.copyrights[0].location.to_string(), "3:4");
+    }
+
+    #[test]
+    fn issues() {
+        assert_eq!(scan("int a;").issues, [Issue::NoLicense]);
+        assert_eq!(
+            scan("// SPDX-License-Identifier: MIT\n// SPDX-License-Identifier: GPL-2.0\n").issues,
+            [Issue::Conflict(vec!["GPL-2.0".into(), "MIT".into()])]
+        );
+        assert_eq!(
+            scan("// SPDX-License-Identifier: MIT OR\n").issues,
+            [Issue::InvalidExpression("MIT OR".into())]
+        );
+    }
+}
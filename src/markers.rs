@@ -0,0 +1,330 @@
+//! Annotation markers in comments
+//!
+//! Finds tags like `TODO(owner): message`, `FIXME`, `NOLINT(check)` or
+//! `SPDX-License-Identifier: MIT` in comments.
+
+use crate::{Lexeme, Lexer, Location, Token};
+use core::ops::Range;
+
+/// Tag argument syntax
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Arg {
+    /// No argument
+    None,
+    /// Argument in parentheses like `TODO(owner)`
+    Parens,
+    /// Argument in brackets like `coverity[event]`
+    Brackets,
+    /// Argument as next word like `lint -e123`
+    Word,
+}
+
+/// Tag pattern
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tag {
+    /// Tag name
+    pub name: String,
+    /// Argument syntax
+    pub arg: Arg,
+    /// Tag should be placed at start of comment
+    pub leading: bool,
+}
+
+impl core::str::FromStr for Tag {
+    type Err = ();
+
+    /// Parse tag pattern
+    ///
+    /// Syntax: `[^]NAME[()|[]| ...]` where `^` means leading tag, `()` and `[]` means
+    /// argument in parentheses or brackets, ` ...` means argument as next word.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (leading, s) = if let Some(s) = s.strip_prefix('^') {
+            (true, s)
+        } else {
+            (false, s)
+        };
+
+        let (name, arg) = if let Some(name) = s.strip_suffix("()") {
+            (name, Arg::Parens)
+        } else if let Some(name) = s.strip_suffix("[]") {
+            (name, Arg::Brackets)
+        } else if let Some(name) = s.strip_suffix(" ...") {
+            (name, Arg::Word)
+        } else {
+            (s, Arg::None)
+        };
+
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(());
+        }
+
+        Ok(Self {
+            name: name.into(),
+            arg,
+            leading,
+        })
+    }
+}
+
+/// Default tag patterns
+pub const DEFAULT_TAGS: &[&str] = &[
+    "TODO()",
+    "FIXME()",
+    "XXX()",
+    "HACK()",
+    "BUG()",
+    "NOLINT()",
+    "NOLINTNEXTLINE()",
+    "NOLINTBEGIN()",
+    "NOLINTEND()",
+    "coverity[]",
+    "^lint ...",
+    "SPDX-License-Identifier",
+];
+
+/// Markers scanning options
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Options {
+    /// Tag patterns
+    pub tags: Vec<Tag>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            tags: DEFAULT_TAGS
+                .iter()
+                .map(|tag| tag.parse().unwrap())
+                .collect(),
+        }
+    }
+}
+
+/// Marker found in comment
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Marker {
+    /// Tag name
+    pub tag: String,
+    /// Owner or tag argument, e.g. `TODO(owner)`, `NOLINT(check)`, `coverity[event]`
+    pub owner: Option<String>,
+    /// Message text
+    pub message: String,
+    /// Tag location in source code
+    pub location: Location,
+    /// Marker span in source code
+    pub span: Range<usize>,
+}
+
+/// Scan comments in source code for markers
+pub fn scan(source: &str, options: &Options) -> Vec<Marker> {
+    let mut markers = Vec::new();
+    for lexeme in Lexer::from(source) {
+        scan_lexeme(&lexeme, options, &mut markers);
+    }
+    markers
+}
+
+/// Scan comment lexeme for markers
+pub fn scan_lexeme(lexeme: &Lexeme, options: &Options, markers: &mut Vec<Marker>) {
+    if lexeme.token != Token::Comment {
+        return;
+    }
+
+    let text = lexeme.slice;
+    let body_end = if text.starts_with("/*") && text.ends_with("*/") && text.len() >= 4 {
+        text.len() - 2
+    } else {
+        text.len()
+    };
+
+    let start = markers.len();
+
+    for tag in &options.tags {
+        for (pos, _) in text[..body_end].match_indices(tag.name.as_str()) {
+            if let Some(marker) = match_tag(lexeme, tag, pos, body_end) {
+                markers.push(marker);
+            }
+        }
+    }
+
+    markers[start..].sort_by_key(|marker| marker.span.start);
+}
+
+fn is_word(chr: char) -> bool {
+    chr.is_alphanumeric() || chr == '_'
+}
+
+fn match_tag(lexeme: &Lexeme, tag: &Tag, pos: usize, body_end: usize) -> Option<Marker> {
+    let text = lexeme.slice;
+    let before = &text[..pos];
+
+    if tag.leading {
+        if !before
+            .chars()
+            .all(|chr| chr == '/' || chr == '*' || chr.is_whitespace())
+        {
+            return None;
+        }
+    } else if before.ends_with(is_word) {
+        return None;
+    }
+
+    let end = pos + tag.name.len();
+    let line_end = text[end..body_end]
+        .find('\n')
+        .map(|len| end + len)
+        .unwrap_or(body_end);
+    let rest = &text[end..line_end];
+
+    if rest.starts_with(is_word) {
+        return None;
+    }
+
+    let (owner, rest) = match tag.arg {
+        Arg::None => (None, rest),
+        Arg::Parens => split_enclosed(rest, '(', ')'),
+        Arg::Brackets => split_enclosed(rest, '[', ']'),
+        Arg::Word => {
+            let rest = rest.trim_start();
+            let len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            if len == 0 {
+                return None;
+            }
+            (Some(&rest[..len]), &rest[len..])
+        }
+    };
+
+    let message = rest
+        .trim_start()
+        .strip_prefix(':')
+        .unwrap_or(rest)
+        .trim()
+        .trim_end_matches('\\')
+        .trim_end();
+
+    Some(Marker {
+        tag: tag.name.clone(),
+        owner: owner.map(|owner| owner.trim().into()),
+        message: message.into(),
+        location: lexeme.location.advance(before),
+        span: lexeme.span.start + pos..lexeme.span.start + line_end,
+    })
+}
+
+fn split_enclosed(text: &str, open: char, close: char) -> (Option<&str>, &str) {
+    if let Some(rest) = text.strip_prefix(open) {
+        if let Some(end) = rest.find(close) {
+            return (Some(&rest[..end]), &rest[end + 1..]);
+        }
+    }
+    (None, text)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn markers(source: &str) -> Vec<(String, Option<String>, String, String)> {
+        scan(source, &Options::default())
+            .into_iter()
+            .map(|marker| {
+                (
+                    marker.tag,
+                    marker.owner,
+                    marker.message,
+                    marker.location.to_string(),
+                )
+            })
+            .collect()
+    }
+
+    fn marker(
+        tag: &str,
+        owner: Option<&str>,
+        message: &str,
+        location: &str,
+    ) -> (String, Option<String>, String, String) {
+        (
+            tag.into(),
+            owner.map(String::from),
+            message.into(),
+            location.into(),
+        )
+    }
+
+    #[test]
+    fn tag_pattern() {
+        assert_eq!(
+            "^lint ...".parse(),
+            Ok(Tag {
+                name: "lint".into(),
+                arg: Arg::Word,
+                leading: true,
+            })
+        );
+        assert_eq!(
+            "TODO()".parse(),
+            Ok(Tag {
+                name: "TODO".into(),
+                arg: Arg::Parens,
+                leading: false,
+            })
+        );
+        assert_eq!("".parse::<Tag>(), Err(()));
+    }
+
+    #[test]
+    fn todo() {
+        assert_eq!(
+            markers(
+                r#"
+int a; // TODO(kayo): remove it
+/* FIXME: overflow
+ * XXX not a TODOS
+ */"#
+            ),
+            [
+                marker("TODO", Some("kayo"), "remove it", "2:11"),
+                marker("FIXME", None, "overflow", "3:4"),
+                marker("XXX", None, "not a TODOS", "4:4"),
+            ]
+        );
+    }
+
+    #[test]
+    fn lint() {
+        assert_eq!(
+            markers(
+                r#"
+int a; // NOLINT(readability-magic-numbers)
+/*lint -e123 not checked */
+// NOLINTNEXTLINE
+/* coverity[overrun-local] */
+/* no lint here */"#
+            ),
+            [
+                marker("NOLINT", Some("readability-magic-numbers"), "", "2:11"),
+                marker("lint", Some("-e123"), "not checked", "3:3"),
+                marker("NOLINTNEXTLINE", None, "", "4:4"),
+                marker("coverity", Some("overrun-local"), "", "5:4"),
+            ]
+        );
+    }
+
+    #[test]
+    fn spdx() {
+        assert_eq!(
+            markers("// SPDX-License-Identifier: GPL-2.0 OR MIT\nint a;"),
+            [marker(
+                "SPDX-License-Identifier",
+                None,
+                "GPL-2.0 OR MIT",
+                "1:4"
+            )]
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! LSP semantic tokens
+//!
+//! Converts lexemes into `textDocument/semanticTokens` data: tokens are split into single
+//! lines, positions are zero-based with columns in UTF-16 code units, and the resulting
+//! array is delta-encoded as five `u32` values per token.
+
+use crate::{directive, Dialect, Keyword, Lexeme, Token};
+
+/// Semantic token type (index in [`TOKEN_TYPES`])
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u32)]
+pub enum TokenType {
+    /// Keyword except type specifiers and modifiers
+    Keyword,
+    /// Type specifier keyword
+    Type,
+    /// Storage class, qualifier or function specifier keyword
+    Modifier,
+    /// Comment
+    Comment,
+    /// String or character literal
+    String,
+    /// Integer or floating-point literal
+    Number,
+    /// Punctuator
+    Operator,
+    /// Preprocessor directive introducer and name
+    Macro,
+}
+
+/// Legend token types in order of [`TokenType`]
+pub const TOKEN_TYPES: &[&str] = &[
+    "keyword", "type", "modifier", "comment", "string", "number", "operator", "macro",
+];
+
+/// Semantic token modifier (bit index in [`TOKEN_MODIFIERS`])
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[repr(u32)]
+pub enum Modifier {
+    /// Documentation comment
+    Documentation,
+    /// Built-in type
+    DefaultLibrary,
+}
+
+/// Legend token modifiers in order of [`Modifier`]
+pub const TOKEN_MODIFIERS: &[&str] = &["documentation", "defaultLibrary"];
+
+impl TokenType {
+    /// LSP name of token type
+    pub fn as_str(self) -> &'static str {
+        TOKEN_TYPES[self as usize]
+    }
+}
+
+impl Modifier {
+    /// LSP name of modifier
+    pub fn as_str(self) -> &'static str {
+        TOKEN_MODIFIERS[self as usize]
+    }
+
+    /// Bit in modifiers set
+    pub fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+/// Semantic tokens legend (`SemanticTokensLegend`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct Legend {
+    /// Token type names
+    pub token_types: &'static [&'static str],
+    /// Token modifier names
+    pub token_modifiers: &'static [&'static str],
+}
+
+/// Legend of encoded tokens
+pub const LEGEND: Legend = Legend {
+    token_types: TOKEN_TYPES,
+    token_modifiers: TOKEN_MODIFIERS,
+};
+
+/// Single line semantic token with absolute position
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SemanticToken {
+    /// Line (starting from 0)
+    pub line: u32,
+    /// Column in UTF-16 code units (starting from 0)
+    pub start: u32,
+    /// Length in UTF-16 code units
+    pub length: u32,
+    /// Token type
+    pub token_type: TokenType,
+    /// Set of [`Modifier`] bits
+    pub modifiers: u32,
+}
+
+/// Classify lexeme out of preprocessor directive context
+///
+/// Identifiers which are not keywords and unknown tokens are not classified.
+pub fn classify(lexeme: &Lexeme, dialect: Dialect) -> Option<(TokenType, u32)> {
+    Some(match lexeme.token {
+        Token::Comment if lexeme.doc().is_some() => {
+            (TokenType::Comment, Modifier::Documentation.bit())
+        }
+        Token::Comment => (TokenType::Comment, 0),
+        Token::String | Token::Char => (TokenType::String, 0),
+        Token::Int | Token::Float => (TokenType::Number, 0),
+        Token::Symbol => (TokenType::Operator, 0),
+        Token::Identifier => {
+            use Keyword::*;
+            match lexeme.keyword_in(dialect)? {
+                keyword if crate::highlight::is_type(keyword) => {
+                    (TokenType::Type, Modifier::DefaultLibrary.bit())
+                }
+                Auto | Register | Static | Extern | ThreadLocal | Const | Volatile | Restrict
+                | Atomic | Inline | NoReturn | AlignAs => (TokenType::Modifier, 0),
+                _ => (TokenType::Keyword, 0),
+            }
+        }
+        Token::Unknown => return None,
+    })
+}
+
+/// Convert lexemes into single line semantic tokens
+///
+/// Directive introducer `#` at line start and following directive name are classified as
+/// [`TokenType::Macro`]. Multi-line tokens (block comments, spliced strings) are split into
+/// one token per line.
+pub fn tokens<'l>(
+    source: &str,
+    dialect: Dialect,
+    lexemes: impl IntoIterator<Item = Lexeme<'l>>,
+) -> Vec<SemanticToken> {
+    let mut tokens = Vec::new();
+    // directive name is expected
+    let mut hash = false;
+
+    for lexeme in lexemes {
+        let directive = matches!(lexeme.slice, "#" | "%:")
+            && lexeme.token == Token::Symbol
+            && directive::at_line_start(source, lexeme.span.start);
+
+        let class = if directive || hash && lexeme.token == Token::Identifier {
+            Some((TokenType::Macro, 0))
+        } else {
+            classify(&lexeme, dialect)
+        };
+
+        if lexeme.token != Token::Comment {
+            hash = directive;
+        }
+
+        let Some((token_type, modifiers)) = class else {
+            continue;
+        };
+
+        let line_start = lexeme.span.start - (lexeme.location.column as usize - 1);
+        let mut start = utf16_len(&source[line_start..lexeme.span.start]);
+        let lines = lexeme.location.line - 1..;
+
+        for (line, part) in lines.zip(lexeme.slice.split('\n')) {
+            let length = utf16_len(part.trim_end_matches('\r'));
+            if length > 0 {
+                tokens.push(SemanticToken {
+                    line,
+                    start,
+                    length,
+                    token_type,
+                    modifiers,
+                });
+            }
+            start = 0;
+        }
+    }
+
+    tokens
+}
+
+fn utf16_len(text: &str) -> u32 {
+    text.chars().map(|chr| chr.len_utf16() as u32).sum()
+}
+
+/// Delta-encode tokens sorted by position into `SemanticTokens.data`
+pub fn encode(tokens: &[SemanticToken]) -> Vec<u32> {
+    let mut data = Vec::with_capacity(tokens.len() * 5);
+    let mut line = 0;
+    let mut start = 0;
+
+    for token in tokens {
+        let delta_line = token.line - line;
+        let delta_start = if delta_line == 0 {
+            token.start - start
+        } else {
+            token.start
+        };
+        data.extend([
+            delta_line,
+            delta_start,
+            token.length,
+            token.token_type as u32,
+            token.modifiers,
+        ]);
+        line = token.line;
+        start = token.start;
+    }
+
+    data
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Lexer;
+
+    fn data(source: &str) -> Vec<[u32; 5]> {
+        encode(&tokens(source, Dialect::default(), Lexer::from(source)))
+            .chunks(5)
+            .map(|chunk| chunk.try_into().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn legend() {
+        assert_eq!(TokenType::Macro.as_str(), "macro");
+        assert_eq!(Modifier::DefaultLibrary.as_str(), "defaultLibrary");
+        assert_eq!(Modifier::DefaultLibrary.bit(), 2);
+    }
+
+    #[test]
+    fn delta() {
+        let (keyword, ty, modifier, comment, string, number, operator, macros) =
+            (0, 1, 2, 3, 4, 5, 6, 7);
+        assert_eq!(
+            data("#define N 1\nstatic int x = N; /** doc */\n  # if"),
+            [
+                [0, 0, 1, macros, 0],
+                [0, 1, 6, macros, 0],
+                [0, 9, 1, number, 0],
+                [1, 0, 6, modifier, 0],
+                [0, 7, 3, ty, 2],
+                [0, 6, 1, operator, 0],
+                [0, 3, 1, operator, 0],
+                [0, 2, 10, comment, 1],
+                [1, 2, 1, macros, 0],
+                [0, 2, 2, macros, 0],
+            ]
+        );
+        assert_eq!(
+            data("a = sizeof \"\\\nx\";"),
+            [
+                [0, 2, 1, operator, 0],
+                [0, 2, 6, keyword, 0],
+                [0, 7, 2, string, 0],
+                [1, 0, 2, string, 0],
+                [0, 2, 1, operator, 0],
+            ]
+        );
+    }
+
+    #[test]
+    fn utf16_columns() {
+        assert_eq!(
+            data("/* é\n𝄞 */ 1"),
+            [[0, 0, 4, 3, 0], [1, 0, 5, 3, 0], [0, 6, 1, 5, 0]]
+        );
+    }
+}
@@ -0,0 +1,311 @@
+//! Source code statistics
+//!
+//! Counts tokens, keywords and literals, and classifies lines into code, comment and blank.
+
+use crate::{Keyword, Lexer, Token};
+use std::collections::BTreeMap;
+
+/// Radix of numeric literal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Radix {
+    /// Binary (`0b`)
+    Binary,
+    /// Octal (`0`)
+    Octal,
+    /// Decimal
+    Decimal,
+    /// Hexadecimal (`0x`)
+    Hexadecimal,
+}
+
+impl Radix {
+    /// Detect radix of integer or floating-point literal
+    pub fn of(literal: &str) -> Self {
+        let prefix = literal.get(..2).unwrap_or_default();
+        if prefix.eq_ignore_ascii_case("0x") {
+            Self::Hexadecimal
+        } else if prefix.eq_ignore_ascii_case("0b") {
+            Self::Binary
+        } else if literal.starts_with('0')
+            && literal[1..].starts_with(|chr: char| chr.is_ascii_digit())
+            && !literal.contains(['.', 'e', 'E'])
+        {
+            Self::Octal
+        } else {
+            Self::Decimal
+        }
+    }
+}
+
+impl core::fmt::Display for Radix {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(match self {
+            Self::Binary => "bin",
+            Self::Octal => "oct",
+            Self::Decimal => "dec",
+            Self::Hexadecimal => "hex",
+        })
+    }
+}
+
+/// Encoding of character or string literal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Encoding {
+    /// No prefix
+    Plain,
+    /// UTF-8 (`u8`)
+    Utf8,
+    /// UTF-16 (`u`)
+    Utf16,
+    /// UTF-32 (`U`)
+    Utf32,
+    /// Wide (`L`)
+    Wide,
+}
+
+impl Encoding {
+    /// Detect encoding of character or string literal
+    pub fn of(literal: &str) -> Self {
+        if literal.starts_with("u8") {
+            Self::Utf8
+        } else if literal.starts_with('u') {
+            Self::Utf16
+        } else if literal.starts_with('U') {
+            Self::Utf32
+        } else if literal.starts_with('L') {
+            Self::Wide
+        } else {
+            Self::Plain
+        }
+    }
+}
+
+impl core::fmt::Display for Encoding {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(match self {
+            Self::Plain => "plain",
+            Self::Utf8 => "u8",
+            Self::Utf16 => "u",
+            Self::Utf32 => "U",
+            Self::Wide => "L",
+        })
+    }
+}
+
+/// Long source line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LongLine {
+    /// Line number (starting from 1)
+    pub line: u32,
+    /// Length in characters
+    pub length: usize,
+}
+
+/// Source code statistics
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Stats {
+    /// Number of lines
+    pub lines: usize,
+    /// Lines with at least one non-comment token (SLOC)
+    pub code_lines: usize,
+    /// Lines with comments only
+    pub comment_lines: usize,
+    /// Lines without tokens
+    pub blank_lines: usize,
+    /// Token counts by kind
+    pub tokens: BTreeMap<Token, usize>,
+    /// Keyword counts
+    pub keywords: BTreeMap<Keyword, usize>,
+    /// Integer literal counts by radix
+    pub ints: BTreeMap<Radix, usize>,
+    /// Floating-point literal counts by radix
+    pub floats: BTreeMap<Radix, usize>,
+    /// Character literal counts by encoding
+    pub chars: BTreeMap<Encoding, usize>,
+    /// String literal counts by encoding
+    pub strings: BTreeMap<Encoding, usize>,
+    /// Longest lines in descending order of length (up to [`Stats::LONGEST`])
+    pub longest: Vec<LongLine>,
+}
+
+impl Stats {
+    /// Number of longest lines to keep
+    pub const LONGEST: usize = 5;
+
+    /// Ratio of comment lines to code lines
+    pub fn comment_ratio(&self) -> f64 {
+        if self.code_lines == 0 {
+            0.0
+        } else {
+            self.comment_lines as f64 / self.code_lines as f64
+        }
+    }
+}
+
+/// Merge counts
+///
+/// Longest lines are not merged because they belong to different sources.
+impl core::ops::AddAssign<&Stats> for Stats {
+    fn add_assign(&mut self, other: &Stats) {
+        fn merge<K: Ord + Copy>(this: &mut BTreeMap<K, usize>, other: &BTreeMap<K, usize>) {
+            for (key, count) in other {
+                *this.entry(*key).or_default() += count;
+            }
+        }
+
+        self.lines += other.lines;
+        self.code_lines += other.code_lines;
+        self.comment_lines += other.comment_lines;
+        self.blank_lines += other.blank_lines;
+        merge(&mut self.tokens, &other.tokens);
+        merge(&mut self.keywords, &other.keywords);
+        merge(&mut self.ints, &other.ints);
+        merge(&mut self.floats, &other.floats);
+        merge(&mut self.chars, &other.chars);
+        merge(&mut self.strings, &other.strings);
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct LineKind {
+    code: bool,
+    comment: bool,
+}
+
+/// Collect statistics of source code
+pub fn scan(source: &str) -> Stats {
+    scan_lexer(Lexer::from(source))
+}
+
+/// Collect statistics using lexer (to use specific dialect)
+pub fn scan_lexer(lexer: Lexer) -> Stats {
+    let source = lexer.source();
+    let dialect = lexer.dialect();
+    let lines = source.lines().collect::<Vec<_>>();
+    let mut kinds = vec![LineKind::default(); lines.len()];
+    let mut stats = Stats {
+        lines: lines.len(),
+        ..Stats::default()
+    };
+
+    for lexeme in lexer {
+        *stats.tokens.entry(lexeme.token).or_default() += 1;
+
+        match lexeme.token {
+            Token::Identifier => {
+                if let Some(keyword) = lexeme.keyword_in(dialect) {
+                    *stats.keywords.entry(keyword).or_default() += 1;
+                }
+            }
+            Token::Int => *stats.ints.entry(Radix::of(lexeme.slice)).or_default() += 1,
+            Token::Float => *stats.floats.entry(Radix::of(lexeme.slice)).or_default() += 1,
+            Token::Char => *stats.chars.entry(Encoding::of(lexeme.slice)).or_default() += 1,
+            Token::String => *stats.strings.entry(Encoding::of(lexeme.slice)).or_default() += 1,
+            _ => {}
+        }
+
+        let first = lexeme.location.line as usize - 1;
+        let last = first + lexeme.slice.trim_end().matches('\n').count();
+        for kind in kinds.iter_mut().take(last + 1).skip(first) {
+            if lexeme.token == Token::Comment {
+                kind.comment = true;
+            } else {
+                kind.code = true;
+            }
+        }
+    }
+
+    for kind in &kinds {
+        if kind.code {
+            stats.code_lines += 1;
+        } else if kind.comment {
+            stats.comment_lines += 1;
+        } else {
+            stats.blank_lines += 1;
+        }
+    }
+
+    let mut longest = lines
+        .iter()
+        .enumerate()
+        .map(|(index, line)| LongLine {
+            line: index as u32 + 1,
+            length: line.chars().count(),
+        })
+        .collect::<Vec<_>>();
+    longest.sort_by(|a, b| b.length.cmp(&a.length).then(a.line.cmp(&b.line)));
+    longest.truncate(Stats::LONGEST);
+    stats.longest = longest;
+
+    stats
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn radix() {
+        assert_eq!(Radix::of("0x1fu"), Radix::Hexadecimal);
+        assert_eq!(Radix::of("0B101"), Radix::Binary);
+        assert_eq!(Radix::of("0755"), Radix::Octal);
+        assert_eq!(Radix::of("0"), Radix::Decimal);
+        assert_eq!(Radix::of("0.5"), Radix::Decimal);
+        assert_eq!(Radix::of("01e3"), Radix::Decimal);
+        assert_eq!(Radix::of("0x1p3"), Radix::Hexadecimal);
+    }
+
+    #[test]
+    fn encoding() {
+        assert_eq!(Encoding::of("u8\"a\""), Encoding::Utf8);
+        assert_eq!(Encoding::of("u'a'"), Encoding::Utf16);
+        assert_eq!(Encoding::of("L\"a\""), Encoding::Wide);
+        assert_eq!(Encoding::of("\"a\""), Encoding::Plain);
+    }
+
+    #[test]
+    fn lines() {
+        let stats = scan(
+            r#"/* header
+ * comment
+ */
+
+int a = 0x10; // trailing
+static const char *s = u8"str";
+
+/* one */ float f = 1.5;
+"#,
+        );
+        assert_eq!(stats.lines, 8);
+        assert_eq!(stats.code_lines, 3);
+        assert_eq!(stats.comment_lines, 3);
+        assert_eq!(stats.blank_lines, 2);
+        assert_eq!(stats.tokens[&Token::Comment], 3);
+        assert_eq!(stats.keywords[&Keyword::Int], 1);
+        assert_eq!(stats.keywords[&Keyword::Static], 1);
+        assert_eq!(stats.ints[&Radix::Hexadecimal], 1);
+        assert_eq!(stats.floats[&Radix::Decimal], 1);
+        assert_eq!(stats.strings[&Encoding::Utf8], 1);
+        assert_eq!(
+            stats.longest[0],
+            LongLine {
+                line: 6,
+                length: 31
+            }
+        );
+        assert_eq!(stats.comment_ratio(), 1.0);
+    }
+
+    #[test]
+    fn merge() {
+        let mut total = scan("int a;\n");
+        total += &scan("// c\nint b;\n");
+        assert_eq!(total.lines, 3);
+        assert_eq!(total.code_lines, 2);
+        assert_eq!(total.keywords[&Keyword::Int], 2);
+    }
+}
@@ -0,0 +1,328 @@
+use crate::{Diagnostic, Dialect, Lexeme, Lexer, Location, Token};
+use core::ops::Range;
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead},
+};
+
+/// Lexeme which owns its slice
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedLexeme {
+    /// Token
+    pub token: Token,
+    /// Location in source
+    pub location: Location,
+    /// Absolute span in source
+    pub span: Range<usize>,
+    /// Token text
+    pub slice: String,
+}
+
+impl OwnedLexeme {
+    /// Borrow as lexeme to extract values
+    pub fn as_lexeme(&self) -> Lexeme<'_> {
+        Lexeme {
+            token: self.token,
+            location: self.location,
+            span: self.span.clone(),
+            slice: &self.slice,
+        }
+    }
+}
+
+impl From<Lexeme<'_>> for OwnedLexeme {
+    fn from(lexeme: Lexeme) -> Self {
+        Self {
+            token: lexeme.token,
+            location: lexeme.location,
+            span: lexeme.span,
+            slice: lexeme.slice.into(),
+        }
+    }
+}
+
+/// Lexer over buffered reader
+///
+/// Source is read in chunks of whole lines. Lexemes which end before the last line of buffered
+/// text are final, the rest of text is lexed again when more input is read, so tokens may
+/// straddle chunks. Memory usage is bounded by twice the longest token or line.
+///
+/// Unfinished text (e.g. huge block comment) is lexed again from its start after each read, so
+/// at least as much text as is pending is read each time to keep total work linear.
+pub struct StreamLexer<R> {
+    reader: R,
+    dialect: Dialect,
+    /// Text which is not lexed yet
+    buffer: String,
+    /// Absolute offset of buffer
+    offset: usize,
+    /// Location of buffer start
+    location: Location,
+    /// Minimum size of text to read before lexing
+    chunk_size: usize,
+    lexemes: VecDeque<OwnedLexeme>,
+    diagnostics: Vec<Diagnostic>,
+    eof: bool,
+}
+
+impl<R: BufRead> StreamLexer<R> {
+    /// Create lexer over reader
+    pub fn new(reader: R) -> Self {
+        Self::with_dialect(reader, Dialect::default())
+    }
+
+    /// Create lexer over reader for specific dialect
+    pub fn with_dialect(reader: R, dialect: Dialect) -> Self {
+        Self {
+            reader,
+            dialect,
+            buffer: String::new(),
+            offset: 0,
+            location: Location::default(),
+            chunk_size: 64 * 1024,
+            lexemes: VecDeque::new(),
+            diagnostics: Vec::new(),
+            eof: false,
+        }
+    }
+
+    /// Set minimum size of text to read before lexing (64 KiB by default)
+    pub fn with_chunk_size(mut self, size: usize) -> Self {
+        self.chunk_size = size.max(1);
+        self
+    }
+
+    /// Language dialect
+    pub fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    /// Diagnostics reported so far
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Take diagnostics reported so far
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        core::mem::take(&mut self.diagnostics)
+    }
+
+    /// Read next chunk of whole lines (at least as long as pending text)
+    fn read(&mut self) -> io::Result<()> {
+        let size = self.chunk_size.max(self.buffer.len());
+        let mut bytes = Vec::new();
+        while bytes.len() < size {
+            if self.reader.read_until(b'\n', &mut bytes)? == 0 {
+                self.eof = true;
+                break;
+            }
+        }
+        let text = String::from_utf8(bytes)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        self.buffer.push_str(&text);
+        Ok(())
+    }
+
+    /// Lex buffered text keeping unfinished tail
+    fn lex(&mut self) {
+        // lexemes ending before last line cannot be changed by following text
+        let limit = if self.eof {
+            usize::MAX
+        } else {
+            match self.buffer.rfind('\n') {
+                Some(pos) => pos + 1,
+                None => return,
+            }
+        };
+
+        let mut lexer = Lexer::with_dialect(&self.buffer, self.dialect);
+        let mut end = 0;
+
+        for lexeme in lexer.by_ref() {
+            if !self.eof && lexeme.span.end >= limit {
+                break;
+            }
+            end = lexeme.span.end;
+            self.lexemes.push_back(OwnedLexeme {
+                token: lexeme.token,
+                location: self.location.join(lexeme.location),
+                span: self.offset + lexeme.span.start..self.offset + lexeme.span.end,
+                slice: lexeme.slice.into(),
+            });
+        }
+
+        let offset = self.offset;
+        let keep = if self.eof { usize::MAX } else { end };
+        self.diagnostics.extend(
+            lexer
+                .take_diagnostics()
+                .into_iter()
+                .filter(|diagnostic| diagnostic.span.start < keep)
+                .map(|diagnostic| Diagnostic {
+                    span: offset + diagnostic.span.start..offset + diagnostic.span.end,
+                    ..diagnostic
+                }),
+        );
+
+        self.location = self
+            .location
+            .join(Location::default().advance(&self.buffer[..end]));
+        self.offset += end;
+        self.buffer.drain(..end);
+    }
+}
+
+impl<R: BufRead> Iterator for StreamLexer<R> {
+    type Item = io::Result<OwnedLexeme>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(lexeme) = self.lexemes.pop_front() {
+                return Some(Ok(lexeme));
+            }
+            if self.eof {
+                return None;
+            }
+            if let Err(error) = self.read() {
+                self.eof = true;
+                return Some(Err(error));
+            }
+            self.lex();
+        }
+    }
+}
+
+/// Buffered reader over sequence of byte chunks
+///
+/// Chunks may split lines and UTF-8 characters anywhere.
+pub struct ChunkReader<I: Iterator> {
+    chunks: I,
+    chunk: Option<I::Item>,
+    pos: usize,
+}
+
+impl<I> ChunkReader<I>
+where
+    I: Iterator,
+    I::Item: AsRef<[u8]>,
+{
+    /// Create reader over chunks
+    pub fn new(chunks: impl IntoIterator<IntoIter = I>) -> Self {
+        Self {
+            chunks: chunks.into_iter(),
+            chunk: None,
+            pos: 0,
+        }
+    }
+}
+
+impl<I> io::Read for ChunkReader<I>
+where
+    I: Iterator,
+    I::Item: AsRef<[u8]>,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<I> BufRead for ChunkReader<I>
+where
+    I: Iterator,
+    I::Item: AsRef<[u8]>,
+{
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        while self
+            .chunk
+            .as_ref()
+            .map(|chunk| self.pos >= chunk.as_ref().len())
+            .unwrap_or(true)
+        {
+            match self.chunks.next() {
+                Some(chunk) => {
+                    self.chunk = Some(chunk);
+                    self.pos = 0;
+                }
+                None => return Ok(&[]),
+            }
+        }
+        Ok(&self.chunk.as_ref().unwrap().as_ref()[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos += amt;
+    }
+}
+
+impl<I> StreamLexer<ChunkReader<I>>
+where
+    I: Iterator,
+    I::Item: AsRef<[u8]>,
+{
+    /// Create lexer over sequence of byte chunks
+    pub fn from_chunks(chunks: impl IntoIterator<IntoIter = I>, dialect: Dialect) -> Self {
+        Self::with_dialect(ChunkReader::new(chunks), dialect)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const SOURCE: &str = "#include <stdio.h>\n/* multi\n line */\nchar *s = \"a\"\n  \"b\\\nc\";\nfloat f = 1.e5 + 0x1.p3; // é\n/* unterminated";
+
+    fn whole(source: &str, dialect: Dialect) -> Vec<OwnedLexeme> {
+        Lexer::with_dialect(source, dialect)
+            .map(Into::into)
+            .collect()
+    }
+
+    #[test]
+    fn chunks() {
+        let bytes = SOURCE.as_bytes();
+
+        for dialect in ["gnu17", "c89"] {
+            let dialect = dialect.parse().unwrap();
+            let expected = whole(SOURCE, dialect);
+            let mut diagnostics = Lexer::with_dialect(SOURCE, dialect);
+            diagnostics.by_ref().for_each(drop);
+
+            for size in 1..bytes.len() {
+                let mut lexer =
+                    StreamLexer::from_chunks(bytes.chunks(size), dialect).with_chunk_size(size);
+                let lexemes = lexer.by_ref().collect::<io::Result<Vec<_>>>().unwrap();
+                assert_eq!(lexemes, expected, "chunk size {}", size);
+                assert_eq!(lexer.diagnostics(), diagnostics.diagnostics());
+            }
+        }
+    }
+
+    #[test]
+    fn long_source() {
+        let line = "int a = \"x\" \"y\"; /* c */\n";
+        let source = line.repeat(64 * 1024 / line.len() * 3);
+        let lexer = StreamLexer::new(source.as_bytes());
+        let lexemes = lexer.collect::<io::Result<Vec<_>>>().unwrap();
+        assert_eq!(lexemes, whole(&source, Dialect::default()));
+    }
+
+    #[test]
+    fn long_comment() {
+        let source = format!("/*{}*/ int a;\n", " comment line\n".repeat(200_000));
+        let lexer = StreamLexer::new(source.as_bytes()).with_chunk_size(64);
+        let lexemes = lexer.collect::<io::Result<Vec<_>>>().unwrap();
+        assert_eq!(lexemes, whole(&source, Dialect::default()));
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let mut lexer = StreamLexer::new(&b"int a;\n\xff\n"[..]);
+        assert!(lexer.next().unwrap().is_err());
+        assert!(lexer.next().is_none());
+    }
+}
@@ -109,10 +109,8 @@ pub fn extract(text: &str) -> Option<String> {
         });
 
         match state {
-            State::Hex(r, n, w) => {
-                if n < w {
-                    out.push(char::from_u32(r).unwrap_or('\0'));
-                }
+            State::Hex(r, n, w) if n < w => {
+                out.push(char::from_u32(r).unwrap_or('\0'));
             }
             State::Oct(r, _) => out.push(r as _),
             _ => {}
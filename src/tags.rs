@@ -0,0 +1,398 @@
+//! Symbol index generation
+//!
+//! Finds macros, functions, prototypes, types, struct members, enum constants and global
+//! variables, and writes them as Universal Ctags (`tags`) or Emacs etags (`TAGS`) files.
+
+use crate::{
+    doc::{declarations, Kind as DeclKind},
+    Keyword, Location,
+};
+
+/// Kind of tag (letters follow Universal Ctags C kinds)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Kind {
+    /// Macro definition (`d`)
+    Macro,
+    /// Enum constant (`e`)
+    Enumerator,
+    /// Function definition (`f`)
+    Function,
+    /// Enum tag (`g`)
+    Enum,
+    /// Struct or union member (`m`)
+    Member,
+    /// Function prototype (`p`)
+    Prototype,
+    /// Struct tag (`s`)
+    Struct,
+    /// Typedef (`t`)
+    Typedef,
+    /// Union tag (`u`)
+    Union,
+    /// Global variable definition (`v`)
+    Variable,
+}
+
+impl Kind {
+    /// Single letter kind
+    pub fn letter(&self) -> char {
+        match self {
+            Self::Macro => 'd',
+            Self::Enumerator => 'e',
+            Self::Function => 'f',
+            Self::Enum => 'g',
+            Self::Member => 'm',
+            Self::Prototype => 'p',
+            Self::Struct => 's',
+            Self::Typedef => 't',
+            Self::Union => 'u',
+            Self::Variable => 'v',
+        }
+    }
+
+    /// Full kind name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Macro => "macro",
+            Self::Enumerator => "enumerator",
+            Self::Function => "function",
+            Self::Enum => "enum",
+            Self::Member => "member",
+            Self::Prototype => "prototype",
+            Self::Struct => "struct",
+            Self::Typedef => "typedef",
+            Self::Union => "union",
+            Self::Variable => "variable",
+        }
+    }
+
+    fn of_tag(keyword: Keyword) -> Self {
+        match keyword {
+            Keyword::Union => Self::Union,
+            Keyword::Enum => Self::Enum,
+            _ => Self::Struct,
+        }
+    }
+}
+
+impl core::fmt::Display for Kind {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Symbol tag
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tag {
+    /// Symbol name
+    pub name: String,
+    /// Tag kind
+    pub kind: Kind,
+    /// Location of name
+    pub location: Location,
+    /// Source line containing name (without line terminator)
+    pub text: String,
+    /// Enclosing struct, union or enum
+    pub scope: Option<(Kind, String)>,
+}
+
+impl Tag {
+    fn new(source: &str, name: &str, kind: Kind, location: Location) -> Self {
+        let start = location.point - (location.column as usize - 1);
+        let text = source[start..]
+            .split('\n')
+            .next()
+            .unwrap_or_default()
+            .trim_end_matches('\r');
+
+        Self {
+            name: name.into(),
+            kind,
+            location,
+            text: text.into(),
+            scope: None,
+        }
+    }
+
+    /// Byte offset of line start
+    pub fn line_offset(&self) -> usize {
+        self.location.point - (self.location.column as usize - 1)
+    }
+}
+
+/// Find tags in source code
+///
+/// External declarations of variables and forward declarations of tags are skipped.
+pub fn scan(source: &str) -> Vec<Tag> {
+    let mut tags = Vec::new();
+
+    for decl in declarations(source) {
+        if let Some(tag) = decl.tag.filter(|tag| tag.defined) {
+            tags.push(Tag {
+                scope: decl
+                    .parent
+                    .map(|(keyword, name)| (Kind::of_tag(keyword), name.into())),
+                ..Tag::new(source, tag.name, Kind::of_tag(tag.keyword), tag.location)
+            });
+        }
+
+        let (Some(name), Some(location)) = (decl.name, decl.location) else {
+            continue;
+        };
+
+        let kind = match decl.kind {
+            DeclKind::Macro => Kind::Macro,
+            DeclKind::Function if decl.typedef => Kind::Typedef,
+            DeclKind::Function if decl.body => Kind::Function,
+            DeclKind::Function => Kind::Prototype,
+            DeclKind::Variable if decl.external => continue,
+            DeclKind::Variable => Kind::Variable,
+            DeclKind::Field => Kind::Member,
+            DeclKind::Constant => Kind::Enumerator,
+            DeclKind::Type if decl.typedef => Kind::Typedef,
+            // struct, union or enum tag is added above
+            DeclKind::Type => continue,
+        };
+
+        let scope = decl
+            .parent
+            .filter(|_| kind != Kind::Macro)
+            .map(|(keyword, name)| (Kind::of_tag(keyword), name.to_string()));
+        for (name, location) in [(name, location)].into_iter().chain(decl.others) {
+            // further declarators of function prototype may declare variables
+            let kind = match kind {
+                Kind::Prototype if name != decl.name.unwrap_or_default() => Kind::Variable,
+                kind => kind,
+            };
+            tags.push(Tag {
+                scope: scope.clone(),
+                ..Tag::new(source, name, kind, location)
+            });
+        }
+    }
+
+    tags
+}
+
+/// Tags of source file
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct File {
+    /// Source file path
+    pub path: String,
+    /// Source size in bytes
+    pub size: usize,
+    /// Tags in order of appearance
+    pub tags: Vec<Tag>,
+}
+
+/// Symbol index of multiple files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Index {
+    /// Indexed files
+    pub files: Vec<File>,
+}
+
+impl Index {
+    /// Scan source file and add its tags
+    pub fn add(&mut self, path: &str, source: &str) {
+        self.files.push(File {
+            path: path.into(),
+            size: source.len(),
+            tags: scan(source),
+        });
+    }
+
+    /// Universal Ctags `tags` file
+    pub fn ctags(&self) -> Ctags<'_> {
+        Ctags(self)
+    }
+
+    /// Emacs `TAGS` file
+    pub fn etags(&self) -> Etags<'_> {
+        Etags(self)
+    }
+}
+
+/// Universal Ctags format of index (sorted by name)
+pub struct Ctags<'i>(&'i Index);
+
+impl core::fmt::Display for Ctags<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        writeln!(
+            f,
+            "!_TAG_FILE_FORMAT\t2\t/extended format; --format=1 will not append ;\" to lines/"
+        )?;
+        writeln!(
+            f,
+            "!_TAG_FILE_SORTED\t1\t/0=unsorted, 1=sorted, 2=foldcase/"
+        )?;
+        writeln!(f, "!_TAG_PROGRAM_NAME\tclex\t//")?;
+        writeln!(
+            f,
+            "!_TAG_PROGRAM_VERSION\t{}\t//",
+            env!("CARGO_PKG_VERSION")
+        )?;
+
+        let mut tags = self
+            .0
+            .files
+            .iter()
+            .flat_map(|file| file.tags.iter().map(move |tag| (file.path.as_str(), tag)))
+            .collect::<Vec<_>>();
+        tags.sort_by(|(a_path, a), (b_path, b)| {
+            (a.name.as_bytes(), a_path, a.location.line).cmp(&(
+                b.name.as_bytes(),
+                b_path,
+                b.location.line,
+            ))
+        });
+
+        for (path, tag) in tags {
+            write!(
+                f,
+                "{}\t{}\t/^{}$/;\"\t{}\tline:{}",
+                tag.name,
+                path,
+                escape_pattern(&tag.text),
+                tag.kind.letter(),
+                tag.location.line
+            )?;
+            if let Some((kind, name)) = &tag.scope {
+                write!(f, "\t{}:{}", kind, name)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Escape search pattern of ctags line
+fn escape_pattern(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for chr in text.chars() {
+        if matches!(chr, '\\' | '/') {
+            out.push('\\');
+        }
+        out.push(chr);
+    }
+    out
+}
+
+/// Emacs etags format of index
+pub struct Etags<'i>(&'i Index);
+
+impl core::fmt::Display for Etags<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        for file in &self.0.files {
+            let mut section = String::new();
+            for tag in &file.tags {
+                // line text up to the end of tag name
+                let end = (tag.location.column as usize - 1 + tag.name.len()).min(tag.text.len());
+                section.push_str(tag.text.get(..end).unwrap_or(&tag.text));
+                section.push('\x7f');
+                section.push_str(&tag.name);
+                section.push('\x01');
+                section.push_str(&format!("{},{}\n", tag.location.line, tag.line_offset()));
+            }
+
+            write!(f, "\x0c\n{},{}\n{}", file.path, section.len(), section)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn tags(source: &str) -> Vec<(&str, char, u32, Option<String>)> {
+        scan(source)
+            .into_iter()
+            .map(|tag| {
+                (
+                    &source[tag.location.point..][..tag.name.len()],
+                    tag.kind.letter(),
+                    tag.location.line,
+                    tag.scope.map(|(kind, name)| format!("{}:{}", kind, name)),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn kinds() {
+        assert_eq!(
+            tags(
+                r#"#define MAX 16
+typedef struct point {
+    int x, y;
+} point_t;
+struct list;
+union value { int i; float f; };
+enum color { RED, GREEN = 2 };
+typedef int (*handler_t)(int);
+extern int errno;
+static int counter = 0;
+int open(const char *path);
+static void close(int fd)
+{
+    int local;
+}
+"#
+            ),
+            [
+                ("MAX", 'd', 1, None),
+                ("x", 'm', 3, Some("struct:point".into())),
+                ("y", 'm', 3, Some("struct:point".into())),
+                ("point", 's', 2, None),
+                ("point_t", 't', 4, None),
+                ("i", 'm', 6, Some("union:value".into())),
+                ("f", 'm', 6, Some("union:value".into())),
+                ("value", 'u', 6, None),
+                ("RED", 'e', 7, Some("enum:color".into())),
+                ("GREEN", 'e', 7, Some("enum:color".into())),
+                ("color", 'g', 7, None),
+                ("handler_t", 't', 8, None),
+                ("counter", 'v', 10, None),
+                ("open", 'p', 11, None),
+                ("close", 'f', 12, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn ctags() {
+        let mut index = Index::default();
+        index.add("b.c", "int b(void) { return 0; }\n");
+        index.add("a.h", "#define A_PATH \"/tmp\\\\\"\nint b(void);\n");
+
+        let ctags = index.ctags().to_string();
+        let lines = ctags.lines().skip(4).collect::<Vec<_>>();
+        assert_eq!(
+            lines,
+            [
+                "A_PATH\ta.h\t/^#define A_PATH \"\\/tmp\\\\\\\\\"$/;\"\td\tline:1",
+                "b\ta.h\t/^int b(void);$/;\"\tp\tline:2",
+                "b\tb.c\t/^int b(void) { return 0; }$/;\"\tf\tline:1",
+            ]
+        );
+        assert!(ctags.starts_with("!_TAG_FILE_FORMAT\t2\t"));
+    }
+
+    #[test]
+    fn etags() {
+        let mut index = Index::default();
+        index.add("a.c", "/* a */\nint a;\nvoid f(void) {}\n");
+
+        assert_eq!(
+            index.etags().to_string(),
+            "\x0c\na.c,26\nint a\x7fa\x012,8\nvoid f\x7ff\x013,15\n"
+        );
+    }
+}
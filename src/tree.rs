@@ -0,0 +1,506 @@
+//! Token trees
+//!
+//! Lexemes are grouped into trees of delimited groups (parentheses, brackets and braces,
+//! including digraphs). Unbalanced delimiters are reported and recovered locally: closing
+//! delimiter of other kind closes enclosing group it matches and is kept as leaf otherwise.
+
+use crate::{Delimiter, Lexeme, Location};
+use core::ops::Range;
+
+/// Token tree
+#[derive(Clone)]
+pub enum TokenTree<'l> {
+    /// Lexeme except group delimiters
+    Leaf(Lexeme<'l>),
+    /// Delimited group
+    Group(Group<'l>),
+}
+
+impl<'l> TokenTree<'l> {
+    /// Byte range in source code
+    pub fn span(&self) -> Range<usize> {
+        match self {
+            Self::Leaf(lexeme) => lexeme.span.clone(),
+            Self::Group(group) => group.span(),
+        }
+    }
+
+    /// Lexemes in source order including delimiters
+    pub fn lexemes(&self) -> Vec<&Lexeme<'l>> {
+        /// Tree to visit or closing delimiter to push after group contents
+        enum Step<'t, 'l> {
+            Tree(&'t TokenTree<'l>),
+            Close(&'t Lexeme<'l>),
+        }
+
+        let mut lexemes = Vec::new();
+        // iterative to support deeply nested groups
+        let mut stack = vec![Step::Tree(self)];
+        while let Some(step) = stack.pop() {
+            match step {
+                Step::Tree(TokenTree::Leaf(lexeme)) | Step::Close(lexeme) => lexemes.push(lexeme),
+                Step::Tree(TokenTree::Group(group)) => {
+                    lexemes.push(&group.open);
+                    stack.extend(group.close.as_ref().map(Step::Close));
+                    stack.extend(group.trees.iter().rev().map(Step::Tree));
+                }
+            }
+        }
+        lexemes
+    }
+}
+
+/// Delimited group
+///
+/// Cloning, dropping and debug formatting are iterative to support deeply nested groups.
+/// Because of `Drop` implementation fields can not be moved out of group, use
+/// [`Group::into_parts`] instead.
+pub struct Group<'l> {
+    /// Kind of delimiters
+    pub delimiter: Delimiter,
+    /// Opening delimiter
+    pub open: Lexeme<'l>,
+    /// Closing delimiter (`None` when group is not closed)
+    pub close: Option<Lexeme<'l>>,
+    /// Trees between delimiters
+    pub trees: Vec<TokenTree<'l>>,
+}
+
+impl<'l> Group<'l> {
+    /// Byte range in source code including delimiters
+    pub fn span(&self) -> Range<usize> {
+        let end = match &self.close {
+            Some(close) => close.span.end,
+            None => self.inner_span().end,
+        };
+        self.open.span.start..end
+    }
+
+    /// Byte range in source code between delimiters
+    pub fn inner_span(&self) -> Range<usize> {
+        let start = self.open.span.end;
+        let end = match (&self.close, self.trees.last()) {
+            (Some(close), _) => close.span.start,
+            (None, Some(last)) => last.span().end,
+            (None, None) => start,
+        };
+        start..end
+    }
+
+    /// Group is closed by matching delimiter
+    pub fn is_closed(&self) -> bool {
+        self.close.is_some()
+    }
+
+    /// Delimiter kind, opening and closing delimiters and trees of group
+    pub fn into_parts(
+        mut self,
+    ) -> (
+        Delimiter,
+        Lexeme<'l>,
+        Option<Lexeme<'l>>,
+        Vec<TokenTree<'l>>,
+    ) {
+        let trees = core::mem::take(&mut self.trees);
+        (self.delimiter, self.open.clone(), self.close.take(), trees)
+    }
+
+    /// Copy of group without trees
+    fn shallow(&self) -> Self {
+        Self {
+            delimiter: self.delimiter,
+            open: self.open.clone(),
+            close: self.close.clone(),
+            trees: Vec::with_capacity(self.trees.len()),
+        }
+    }
+}
+
+impl<'l> Clone for Group<'l> {
+    fn clone(&self) -> Self {
+        // original groups with copies of their trees cloned so far
+        let mut stack = vec![(self, self.shallow())];
+        loop {
+            let (original, copy) = stack.last_mut().unwrap();
+            let original: &Group<'l> = original;
+            match original.trees.get(copy.trees.len()) {
+                Some(TokenTree::Leaf(lexeme)) => copy.trees.push(TokenTree::Leaf(lexeme.clone())),
+                Some(TokenTree::Group(group)) => stack.push((group, group.shallow())),
+                None => {
+                    let (_, copy) = stack.pop().unwrap();
+                    match stack.last_mut() {
+                        Some((_, parent)) => parent.trees.push(TokenTree::Group(copy)),
+                        None => break copy,
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Drop for Group<'_> {
+    fn drop(&mut self) {
+        let mut trees = core::mem::take(&mut self.trees);
+        while let Some(tree) = trees.pop() {
+            if let TokenTree::Group(mut group) = tree {
+                trees.append(&mut group.trees);
+            }
+        }
+    }
+}
+
+/// Part of debug output of trees
+enum Debugged<'t, 'l> {
+    Tree(&'t TokenTree<'l>),
+    Group(&'t Group<'l>),
+    Text(&'static str),
+}
+
+impl Debugged<'_, '_> {
+    /// Format like derived `Debug` without recursion (always on single line)
+    fn fmt(self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let mut stack = vec![self];
+        while let Some(part) = stack.pop() {
+            match part {
+                Self::Text(text) => f.write_str(text)?,
+                Self::Tree(TokenTree::Leaf(lexeme)) => write!(f, "Leaf({:?})", lexeme)?,
+                Self::Tree(TokenTree::Group(group)) => {
+                    f.write_str("Group(")?;
+                    stack.push(Self::Text(")"));
+                    stack.push(Self::Group(group));
+                }
+                Self::Group(group) => {
+                    write!(
+                        f,
+                        "Group {{ delimiter: {:?}, open: {:?}, close: {:?}, trees: [",
+                        group.delimiter, group.open, group.close
+                    )?;
+                    stack.push(Self::Text("] }"));
+                    for (position, tree) in group.trees.iter().enumerate().rev() {
+                        stack.push(Self::Tree(tree));
+                        if position > 0 {
+                            stack.push(Self::Text(", "));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl core::fmt::Debug for TokenTree<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        Debugged::Tree(self).fmt(f)
+    }
+}
+
+impl core::fmt::Debug for Group<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        Debugged::Group(self).fmt(f)
+    }
+}
+
+/// Position of delimiter
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Position {
+    /// Delimiter kind
+    pub delimiter: Delimiter,
+    /// Location in source code
+    pub location: Location,
+    /// Byte range in source code
+    pub span: Range<usize>,
+}
+
+impl From<(Delimiter, &Lexeme<'_>)> for Position {
+    fn from((delimiter, lexeme): (Delimiter, &Lexeme<'_>)) -> Self {
+        Self {
+            delimiter,
+            location: lexeme.location,
+            span: lexeme.span.clone(),
+        }
+    }
+}
+
+/// Kind of unbalanced delimiters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UnbalancedKind {
+    /// Group is not closed until end
+    Unclosed,
+    /// Closing delimiter without group
+    Unopened,
+    /// Closing delimiter does not match innermost group
+    Mismatched,
+}
+
+/// Unbalanced delimiters
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Unbalanced {
+    /// Error kind
+    pub kind: UnbalancedKind,
+    /// Opening delimiter (except unopened)
+    pub open: Option<Position>,
+    /// Closing delimiter (except unclosed)
+    pub close: Option<Position>,
+}
+
+impl core::fmt::Display for Unbalanced {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let open = |position: &Position| position.delimiter.symbols().0;
+        let close = |position: &Position| position.delimiter.symbols().1;
+        match (&self.open, &self.close) {
+            (Some(open_at), Some(close_at)) => write!(
+                f,
+                "mismatched closing delimiter `{}` at {} for `{}` at {}",
+                close(close_at),
+                close_at.location,
+                open(open_at),
+                open_at.location
+            ),
+            (Some(open_at), None) => {
+                write!(
+                    f,
+                    "unclosed delimiter `{}` at {}",
+                    open(open_at),
+                    open_at.location
+                )
+            }
+            (None, Some(close_at)) => write!(
+                f,
+                "unexpected closing delimiter `{}` at {}",
+                close(close_at),
+                close_at.location
+            ),
+            (None, None) => f.write_str("unbalanced delimiters"),
+        }
+    }
+}
+
+/// Token trees of source code
+#[derive(Debug, Clone, Default)]
+pub struct TokenTrees<'l> {
+    /// Top level trees
+    pub trees: Vec<TokenTree<'l>>,
+    /// Unbalanced delimiters in source order of detection
+    pub errors: Vec<Unbalanced>,
+}
+
+impl<'l> TokenTrees<'l> {
+    /// All groups in depth-first order
+    pub fn groups(&self) -> Vec<&Group<'l>> {
+        let mut groups = Vec::new();
+        let mut stack = self.trees.iter().rev().collect::<Vec<_>>();
+        while let Some(tree) = stack.pop() {
+            if let TokenTree::Group(group) = tree {
+                groups.push(group);
+                stack.extend(group.trees.iter().rev());
+            }
+        }
+        groups
+    }
+}
+
+/// Build token trees from lexemes
+pub fn parse<'l>(lexemes: impl IntoIterator<Item = Lexeme<'l>>) -> TokenTrees<'l> {
+    let mut errors = Vec::new();
+    // top level trees and open groups
+    let mut trees = Vec::new();
+    let mut stack: Vec<Group<'l>> = Vec::new();
+
+    fn push<'l>(stack: &mut [Group<'l>], trees: &mut Vec<TokenTree<'l>>, tree: TokenTree<'l>) {
+        match stack.last_mut() {
+            Some(group) => group.trees.push(tree),
+            None => trees.push(tree),
+        }
+    }
+
+    for lexeme in lexemes {
+        if let Some(delimiter) = Delimiter::open(&lexeme) {
+            stack.push(Group {
+                delimiter,
+                open: lexeme,
+                close: None,
+                trees: Vec::new(),
+            });
+        } else if let Some(delimiter) = Delimiter::close(&lexeme) {
+            let close = Position::from((delimiter, &lexeme));
+            match stack.iter().rposition(|group| group.delimiter == delimiter) {
+                Some(depth) => {
+                    // inner groups are closed implicitly
+                    while stack.len() > depth + 1 {
+                        let group = stack.pop().unwrap();
+                        errors.push(Unbalanced {
+                            kind: UnbalancedKind::Mismatched,
+                            open: Some(Position::from((group.delimiter, &group.open))),
+                            close: Some(close.clone()),
+                        });
+                        push(&mut stack, &mut trees, TokenTree::Group(group));
+                    }
+                    let mut group = stack.pop().unwrap();
+                    group.close = Some(lexeme);
+                    push(&mut stack, &mut trees, TokenTree::Group(group));
+                }
+                None => {
+                    errors.push(Unbalanced {
+                        kind: match stack.last() {
+                            Some(_) => UnbalancedKind::Mismatched,
+                            None => UnbalancedKind::Unopened,
+                        },
+                        open: stack
+                            .last()
+                            .map(|group| Position::from((group.delimiter, &group.open))),
+                        close: Some(close),
+                    });
+                    push(&mut stack, &mut trees, TokenTree::Leaf(lexeme));
+                }
+            }
+        } else {
+            push(&mut stack, &mut trees, TokenTree::Leaf(lexeme));
+        }
+    }
+
+    let mut unclosed = Vec::new();
+    while let Some(group) = stack.pop() {
+        unclosed.push(Unbalanced {
+            kind: UnbalancedKind::Unclosed,
+            open: Some(Position::from((group.delimiter, &group.open))),
+            close: None,
+        });
+        push(&mut stack, &mut trees, TokenTree::Group(group));
+    }
+    errors.extend(unclosed.into_iter().rev());
+
+    TokenTrees { trees, errors }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Lexer;
+
+    /// Render trees in compact form
+    fn render(trees: &[TokenTree]) -> String {
+        trees
+            .iter()
+            .map(|tree| match tree {
+                TokenTree::Leaf(lexeme) => lexeme.slice.to_string(),
+                TokenTree::Group(group) => format!(
+                    "{}{}{}",
+                    group.open.slice,
+                    render(&group.trees),
+                    group.close.as_ref().map(|close| close.slice).unwrap_or("?")
+                ),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn errors(source: &str) -> Vec<String> {
+        parse(Lexer::from(source))
+            .errors
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn balanced() {
+        let source = "int f(int a[2]) { return g(a, <%1%>); }";
+        let trees = parse(Lexer::from(source));
+        assert!(trees.errors.is_empty());
+        assert_eq!(
+            render(&trees.trees),
+            "int f (int a [2]) {return g (a , <%1%>) ;}"
+        );
+        let groups = trees.groups();
+        assert_eq!(
+            groups
+                .iter()
+                .map(|group| &source[group.span()])
+                .collect::<Vec<_>>(),
+            [
+                "(int a[2])",
+                "[2]",
+                "{ return g(a, <%1%>); }",
+                "(a, <%1%>)",
+                "<%1%>"
+            ]
+        );
+        assert_eq!(&source[groups[2].inner_span()], " return g(a, <%1%>); ");
+        assert_eq!(trees.trees[2].lexemes().len(), 7);
+    }
+
+    #[test]
+    fn unbalanced() {
+        assert_eq!(
+            errors("a)\n(b"),
+            [
+                "unexpected closing delimiter `)` at 1:2",
+                "unclosed delimiter `(` at 2:1"
+            ]
+        );
+        assert_eq!(
+            errors("{ f(x; }"),
+            ["mismatched closing delimiter `}` at 1:8 for `(` at 1:4"]
+        );
+        assert_eq!(
+            errors("( ] )"),
+            ["mismatched closing delimiter `]` at 1:3 for `(` at 1:1"]
+        );
+        assert_eq!(
+            errors("{ [ ("),
+            [
+                "unclosed delimiter `{` at 1:1",
+                "unclosed delimiter `[` at 1:3",
+                "unclosed delimiter `(` at 1:5"
+            ]
+        );
+    }
+
+    #[test]
+    fn recovery() {
+        let trees = parse(Lexer::from("{ f(x; } g(y) ]"));
+        assert_eq!(render(&trees.trees), "{f (x ;?} g (y) ]");
+        let TokenTree::Group(group) = &trees.trees[0] else {
+            unreachable!()
+        };
+        assert!(group.is_closed());
+        let TokenTree::Group(inner) = &group.trees[1] else {
+            unreachable!()
+        };
+        assert!(!inner.is_closed());
+        assert_eq!(inner.span(), 3..6);
+        let (delimiter, open, close, trees) = inner.clone().into_parts();
+        assert_eq!(delimiter, Delimiter::Paren);
+        assert_eq!((open.slice, close.is_none(), trees.len()), ("(", true, 2));
+
+        let trees = parse(Lexer::from("f(a"));
+        let TokenTree::Group(group) = &trees.trees[1] else {
+            unreachable!()
+        };
+        assert_eq!(group.span(), 1..3);
+        assert_eq!(trees.errors[0].kind, UnbalancedKind::Unclosed);
+    }
+
+    #[test]
+    fn deep() {
+        let depth = 200_000;
+        let source = format!("{}x{}", "(".repeat(depth), ")".repeat(depth));
+        let trees = parse(Lexer::from(source.as_str()));
+        assert!(trees.errors.is_empty());
+        assert_eq!(trees.groups().len(), depth);
+        let copy = trees.trees[0].clone();
+        assert_eq!(copy.lexemes().len(), 2 * depth + 1);
+        let debug = format!("{:?}", copy);
+        assert!(debug.starts_with("Group(Group { delimiter: Paren"));
+        assert_eq!(debug.matches("Leaf(").count(), 1);
+        drop(trees);
+
+        let source = "(".repeat(depth);
+        let trees = parse(Lexer::from(source.as_str()));
+        assert_eq!(trees.errors.len(), depth);
+    }
+}